@@ -0,0 +1,192 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::cardset::{CardSet, COMPLETION};
+
+/// The most cards that can be on the table without a set among them
+pub const MAX_CAP_SIZE: usize = 20;
+
+// A collection of cards without a set among them is called a cap.
+
+impl CardSet {
+    /// The cards that make a set with two of these cards
+    pub fn completions(&self) -> CardSet {
+        let indices: Vec<u8> = self.indices().collect();
+        let mut completions = CardSet::empty();
+        for (n, &first) in indices.iter().enumerate() {
+            for &second in &indices[n + 1..] {
+                completions.insert_index(COMPLETION[first as usize][second as usize]);
+            }
+        }
+        completions
+    }
+
+    /// Whether there is no set among these cards
+    pub fn is_cap(&self) -> bool {
+        !self.has_set()
+    }
+
+    /// The cards that can be added without making a set
+    pub fn extensions(&self) -> CardSet {
+        CardSet::full().difference(self).difference(&self.completions())
+    }
+
+    /// Whether a card can be added without making a set, to cards that have no set yet
+    pub fn can_extend(&self) -> bool {
+        self.is_cap() && !self.extensions().is_empty()
+    }
+
+    /// A cap that no card can be added to without making a set
+    pub fn is_maximal_cap(&self) -> bool {
+        self.is_cap() && self.extensions().is_empty()
+    }
+
+    /// Number of cards in `deck` that would make a set with two of these cards
+    pub fn count_set_makers(&self, deck: &CardSet) -> usize {
+        self.completions().intersection(deck).difference(self).len()
+    }
+}
+
+/// Add random cards to a cap until no card can be added without making a set.
+/// There is none when `start` has a set already.
+pub fn random_maximal_cap<R: Rng>(start: CardSet, rng: &mut R) -> Option<CardSet> {
+    if !start.is_cap() {
+        return None;
+    }
+    let mut cap = start;
+    loop {
+        let extensions: Vec<u8> = cap.extensions().indices().collect();
+        match extensions.choose(rng) {
+            Some(&index) => cap.insert_index(index),
+            None => return Some(cap),
+        }
+    }
+}
+
+/// A largest cap that contains `start`. There is none when `start` has a set already.
+/// The search stops early when it finds 20 cards. Otherwise it has to rule out every larger cap: every card
+/// that can be added is tried both in and out of the cap, and a branch is only cut off when even all cards left
+/// cannot beat the best cap. The worst case is exponential in the number of cards that can be added to `start`,
+/// and is reached by starts that are in no cap of 20, such as some caps of 8 cards of the same color.
+pub fn largest_cap(start: CardSet) -> Option<CardSet> {
+    if !start.is_cap() {
+        return None;
+    }
+    let mut search = CapSearch { best: start };
+    search.extend(start, start.extensions());
+    Some(search.best)
+}
+
+/// Depth first search over the cards that can be added, in the order of their index
+struct CapSearch {
+    best: CardSet,
+}
+
+impl CapSearch {
+    fn done(&self) -> bool {
+        self.best.len() == MAX_CAP_SIZE
+    }
+
+    fn extend(&mut self, cap: CardSet, mut available: CardSet) {
+        if cap.len() > self.best.len() {
+            self.best = cap;
+        }
+        while let Some(index) = available.indices().next() {
+            // Even taking every available card does not beat the best cap
+            if self.done() || cap.len() + available.len() <= self.best.len() {
+                return;
+            }
+            // Caps with this card are all found in this branch, so later branches go without it
+            available.remove_index(index);
+            let mut next_available = available;
+            for card in cap.indices() {
+                next_available.remove_index(COMPLETION[card as usize][index as usize]);
+            }
+            let mut next_cap = cap;
+            next_cap.insert_index(index);
+            self.extend(next_cap, next_available);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_all_cards, Card, Deck};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn cards(notations: &[&str]) -> CardSet {
+        notations.iter().map(|notation| notation.parse::<Card>().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_extensions() {
+        let table = cards(&["1RFd", "2RFd", "1GFd"]);
+        assert!(table.is_cap());
+        assert_eq!(table.completions(), cards(&["3RFd", "1PFd", "3PFd"]));
+        assert_eq!(table.extensions().len(), 81 - 3 - 3);
+        assert!(table.can_extend());
+        assert!(!table.is_maximal_cap());
+        assert!(!cards(&["1RFd", "2RFd", "3RFd"]).is_cap());
+
+        // Every card added from the extensions keeps the table free of sets
+        for card in table.extensions().iter() {
+            let mut bigger = table;
+            bigger.insert(&card);
+            assert!(bigger.is_cap());
+        }
+    }
+
+    #[test]
+    fn test_count_set_makers() {
+        let mut deck = Deck::shuffled(&mut ChaCha8Rng::seed_from_u64(4));
+        let table = deck.deal(12);
+        let deck: CardSet = deck.deal(81).iter().collect();
+        let table_set: CardSet = table.iter().collect();
+
+        let brute_force = deck
+            .iter()
+            .filter(|card| table.iter().enumerate().any(|(n, a)| table[n + 1..].iter().any(|b| a.complete_set(b) == *card)))
+            .count();
+        assert_eq!(table_set.count_set_makers(&deck), brute_force);
+    }
+
+    #[test]
+    fn test_random_maximal_cap() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        for _ in 0..20 {
+            let cap = random_maximal_cap(CardSet::empty(), &mut rng).unwrap();
+            assert!(cap.is_maximal_cap());
+            assert!(!cap.can_extend());
+            assert!(cap.len() <= MAX_CAP_SIZE);
+        }
+        let table = cards(&["1RFd", "2GOo"]);
+        let cap = random_maximal_cap(table, &mut rng).unwrap();
+        assert_eq!(cap.intersection(&table), table);
+        assert_eq!(random_maximal_cap(cards(&["1RFd", "2RFd", "3RFd"]), &mut rng), None);
+    }
+
+    #[test]
+    fn test_largest_cap() {
+        let cap = largest_cap(CardSet::empty()).unwrap();
+        assert_eq!(cap.len(), MAX_CAP_SIZE);
+        assert!(cap.is_maximal_cap());
+
+        let table: CardSet = generate_all_cards()[40..43].iter().collect();
+        let cap = largest_cap(table).unwrap();
+        assert_eq!(cap.len(), MAX_CAP_SIZE);
+        assert_eq!(cap.intersection(&table), table);
+        assert_eq!(largest_cap(cards(&["1RFd", "2RFd", "3RFd"])), None);
+    }
+
+    #[test]
+    fn test_largest_cap_below_max() {
+        // 8 red cards without a set, which leave room for only 18, so the whole search runs
+        let table = cards(&["1ROs", "2ROd", "2ROs", "2RSo", "3ROd", "3RFo", "3RFs", "3RSd"]);
+        let cap = largest_cap(table).unwrap();
+        assert_eq!(cap.len(), 18);
+        assert_eq!(cap.intersection(&table), table);
+        assert!(cap.is_maximal_cap());
+    }
+}
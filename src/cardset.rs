@@ -0,0 +1,304 @@
+use std::fmt;
+
+use crate::{Card, ALL_CARDS};
+
+/// Number of different cards
+pub const CARD_COUNT: u8 = 81;
+
+// A card is numbered by writing its `values()` as base-3 digits.
+// This numbers the cards in the order of `generate_all_cards`.
+
+/// The base-3 digits of a card number, which are the `values()` of that card
+pub(crate) const fn digits(index: u8) -> [u8; 4] {
+    [index / 27, index / 9 % 3, index / 3 % 3, index % 3]
+}
+
+/// The card number with these base-3 digits
+pub(crate) const fn from_digits(digits: [u8; 4]) -> u8 {
+    ((digits[0] * 3 + digits[1]) * 3 + digits[2]) * 3 + digits[3]
+}
+
+impl Card {
+    /// The number of this card, from 0 to 80
+    pub fn index(&self) -> u8 {
+        from_digits(self.values())
+    }
+
+    /// The card with this number, if it is below 81
+    pub fn from_index(index: u8) -> Option<Card> {
+        ALL_CARDS.get(usize::from(index)).copied()
+    }
+}
+
+/// For every digit: the same when both are the same, otherwise the third digit
+const fn complete_index(a: u8, b: u8) -> u8 {
+    let (a, b) = (digits(a), digits(b));
+    let mut result = [0; 4];
+    let mut i = 0;
+    while i < 4 {
+        result[i] = (6 - a[i] - b[i]) % 3;
+        i += 1;
+    }
+    from_digits(result)
+}
+
+const fn completion_table() -> [[u8; 81]; 81] {
+    let mut table = [[0; 81]; 81];
+    let mut a = 0;
+    while a < 81 {
+        let mut b = 0;
+        while b < 81 {
+            table[a][b] = complete_index(a as u8, b as u8);
+            b += 1;
+        }
+        a += 1;
+    }
+    table
+}
+
+/// The index of the card that completes the set, for every pair of card indices
+pub(crate) static COMPLETION: [[u8; 81]; 81] = completion_table();
+
+/// A set of cards (in the mathematical sense) stored as one bit per card,
+/// to quickly deal tables and search them for sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CardSet(u128);
+
+impl CardSet {
+    pub const fn empty() -> Self {
+        CardSet(0)
+    }
+
+    /// All 81 cards
+    pub const fn full() -> Self {
+        CardSet((1 << CARD_COUNT) - 1)
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        self.contains_index(card.index())
+    }
+
+    pub fn contains_index(&self, index: u8) -> bool {
+        index < CARD_COUNT && self.0 & (1 << index) != 0
+    }
+
+    pub fn insert(&mut self, card: &Card) {
+        self.insert_index(card.index());
+    }
+
+    /// Panics when there is no card with this index, as setting an unused bit would break `len` and `full`
+    pub fn insert_index(&mut self, index: u8) {
+        assert!(index < CARD_COUNT, "There is no card {index}");
+        self.0 |= 1 << index;
+    }
+
+    pub fn remove(&mut self, card: &Card) {
+        self.remove_index(card.index());
+    }
+
+    /// Panics when there is no card with this index
+    pub fn remove_index(&mut self, index: u8) {
+        assert!(index < CARD_COUNT, "There is no card {index}");
+        self.0 &= !(1 << index);
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    /// The cards in this set that are not in `other`
+    pub fn difference(&self, other: &CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    /// The indices of the cards, from low to high
+    pub fn indices(&self) -> Indices {
+        Indices(self.0)
+    }
+
+    /// The cards, in the order of their index
+    pub fn iter(&self) -> impl Iterator<Item = Card> {
+        self.indices().map(|index| Card::from_index(index).unwrap())
+    }
+
+    /// All sets among the cards, as indices in increasing order
+    pub fn set_indices(&self) -> impl Iterator<Item = [u8; 3]> + '_ {
+        self.indices().flat_map(move |first| {
+            // Only pair with the cards after `first`, so every set is found once
+            let later = Indices(self.0 & !((2u128 << first) - 1));
+            later.filter_map(move |second| {
+                let third = COMPLETION[first as usize][second as usize];
+                (third > second && self.contains_index(third)).then_some([first, second, third])
+            })
+        })
+    }
+
+    /// All sets among the cards, every set in the order of the card indices
+    pub fn find_all_sets(&self) -> Vec<[Card; 3]> {
+        self.set_indices().map(cards_of).collect()
+    }
+
+    /// The first set in the order of the card indices
+    pub fn find_set(&self) -> Option<[Card; 3]> {
+        self.set_indices().next().map(cards_of)
+    }
+
+    pub fn has_set(&self) -> bool {
+        self.set_indices().next().is_some()
+    }
+
+    pub fn count_sets(&self) -> usize {
+        self.set_indices().count()
+    }
+}
+
+fn cards_of(indices: [u8; 3]) -> [Card; 3] {
+    indices.map(|index| Card::from_index(index).unwrap())
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> Self {
+        let mut set = CardSet::empty();
+        for card in cards {
+            set.insert(&card);
+        }
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(cards: I) -> Self {
+        cards.into_iter().copied().collect()
+    }
+}
+
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let notations: Vec<String> = self.iter().map(|card| card.notation()).collect();
+        write!(f, "{{{}}}", notations.join(" "))
+    }
+}
+
+/// Iterator over the indices of the cards in a `CardSet`
+pub struct Indices(u128);
+
+impl Iterator for Indices {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        // Clear the lowest bit
+        self.0 &= self.0 - 1;
+        Some(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_all_sets, generate_all_cards, Deck};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_index() {
+        for (index, card) in generate_all_cards().iter().enumerate() {
+            assert_eq!(card.index() as usize, index);
+            assert_eq!(Card::from_index(index as u8), Some(*card));
+        }
+        assert_eq!(Card::from_index(81), None);
+    }
+
+    #[test]
+    fn test_complete_index() {
+        for a in generate_all_cards() {
+            for b in generate_all_cards() {
+                let third = COMPLETION[a.index() as usize][b.index() as usize];
+                assert_eq!(Card::from_index(third), Some(a.complete_set(&b)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_card_set_operations() {
+        let a: CardSet = ["1RFd", "2GOo"].map(card).iter().collect();
+        let b: CardSet = ["2GOo", "3PSs"].map(card).iter().collect();
+        assert_eq!(a.len(), 2);
+        assert!(a.contains(&card("1RFd")));
+        assert!(!a.contains(&card("3PSs")));
+        assert_eq!(a.union(&b).len(), 3);
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [card("2GOo")]);
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), [card("1RFd")]);
+        assert_eq!(CardSet::full().len(), 81);
+        assert!(CardSet::full().difference(&CardSet::full()).is_empty());
+
+        let mut c = a;
+        c.remove(&card("1RFd"));
+        c.insert(&card("3PSs"));
+        assert_eq!(c, b);
+        assert_eq!(c.to_string(), "{2GOo 3PSs}");
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no card 81")]
+    fn test_insert_index_out_of_range() {
+        CardSet::empty().insert_index(81);
+    }
+
+    #[test]
+    #[should_panic(expected = "There is no card 127")]
+    fn test_remove_index_out_of_range() {
+        CardSet::full().remove_index(127);
+    }
+
+    #[test]
+    fn test_find_sets() {
+        assert_eq!(CardSet::full().count_sets(), 1080);
+        assert_eq!(CardSet::empty().find_set(), None);
+
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for _ in 0..100 {
+            let table = Deck::shuffled(&mut rng).deal(12);
+            let set: CardSet = table.iter().collect();
+
+            let mut expected: Vec<[Card; 3]> = find_all_sets(&table)
+                .iter()
+                .map(|triple| {
+                    let mut cards = triple.cards();
+                    cards.sort_by_key(Card::index);
+                    cards
+                })
+                .collect();
+            expected.sort_by_key(|cards| cards.map(|card| card.index()));
+
+            assert_eq!(set.find_all_sets(), expected);
+            assert_eq!(set.count_sets(), expected.len());
+            assert_eq!(set.has_set(), !expected.is_empty());
+            assert_eq!(set.find_set(), expected.first().copied());
+        }
+    }
+}
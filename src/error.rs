@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::{Attribute, ParseTableError};
+
+/// Everything that can go wrong in this crate
+#[derive(Debug)]
+pub enum Error {
+    /// A value that no card has, like a count of 4
+    InvalidAttribute { attribute: Attribute, value: String },
+    /// A table that could not be parsed
+    Parse(ParseTableError),
+    /// A table that cannot be laid out, like one whose number of cards is not a multiple of 3
+    MalformedTable(String),
+    /// There is no set among the cards
+    NoSet,
+    /// Rules of a Set variant that cannot be played
+    InvalidVariant(String),
+    /// No table was found with the requested sets
+    Puzzle(String),
+    ImageDecode(image::ImageError),
+    /// An image that could not be written to this path
+    ImageEncode { path: PathBuf, error: image::ImageError },
+    /// No cards could be recognised in a photo
+    Recognition(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidAttribute { attribute, value } => write!(f, "invalid {attribute} '{value}'"),
+            Error::Parse(error) => write!(f, "could not parse table: {error}"),
+            Error::MalformedTable(reason) => write!(f, "malformed table: {reason}"),
+            Error::NoSet => write!(f, "there is no set among these cards"),
+            Error::InvalidVariant(reason) => write!(f, "invalid variant: {reason}"),
+            Error::Puzzle(reason) => write!(f, "could not generate a puzzle: {reason}"),
+            Error::ImageDecode(error) => write!(f, "could not decode image: {error}"),
+            Error::ImageEncode { path, error } => write!(f, "could not write image {}: {error}", path.display()),
+            Error::Recognition(reason) => write!(f, "could not recognise the cards: {reason}"),
+            Error::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::ImageDecode(error) => Some(error),
+            Error::ImageEncode { error, .. } => Some(error),
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseTableError> for Error {
+    fn from(error: ParseTableError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    /// An image that could not be read: a file that cannot be opened is an `Io` error
+    fn from(error: image::ImageError) -> Self {
+        match error {
+            image::ImageError::IoError(error) => Error::Io(error),
+            error => Error::ImageDecode(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_table, Count};
+    use std::error::Error as _;
+
+    #[test]
+    fn test_display_error() {
+        let error = Count::try_from(4).unwrap_err();
+        assert_eq!(error.to_string(), "invalid count '4'");
+
+        let error = Error::from(parse_table("2RSo 4RSo").unwrap_err());
+        assert_eq!(
+            error.to_string(),
+            "could not parse table: line 1, column 6: '4RSo': invalid count '4', expected 1, 2 or 3"
+        );
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn test_image_errors() {
+        let missing = image::open("does/not/exist.png").unwrap_err();
+        assert!(matches!(Error::from(missing), Error::Io(_)));
+
+        let image = image::RgbImage::new(1, 1);
+        let path = PathBuf::from("no_such_directory/out.png");
+        let error = image.save(&path).unwrap_err();
+        let error = Error::ImageEncode { path, error };
+        assert!(error.to_string().starts_with("could not write image no_such_directory/out.png: "), "{error}");
+    }
+}
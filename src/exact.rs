@@ -0,0 +1,360 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::thread;
+
+use crate::cardset::{digits, from_digits, CardSet, CARD_COUNT, COMPLETION};
+
+// Cards are the points of the affine space AG(4,3): the index of a card written in base 3
+// gives its 4 coordinates. The affine maps of that space map sets to sets, so tables that are
+// mapped to each other have the same number of sets. Counting is done for one table of every
+// orbit of small tables, and then extended to the full size by brute force.
+
+/// Number of invertible 4 by 4 matrices over the field with 3 elements
+const GL4_ORDER: u128 = 24_261_120;
+/// Number of affine maps of AG(4,3)
+const AFFINE_ORDER: u128 = CARD_COUNT as u128 * GL4_ORDER;
+/// Number of invertible n by n matrices over the field with 3 elements, by n
+const GL_ORDER: [u128; 5] = [1, 2, 48, 11_232, GL4_ORDER];
+
+/// The largest tables to find all orbits of, as finding the canonical form of larger tables gets slow
+const MAX_ORBIT_SIZE: usize = 8;
+/// Number of cards to add by brute force to every orbit
+const BRUTE_FORCE_CARDS: usize = 4;
+
+/// The vector from `b` to `a`
+fn sub(a: u8, b: u8) -> u8 {
+    let (a, b) = (digits(a), digits(b));
+    from_digits([0, 1, 2, 3].map(|i| (a[i] + 3 - b[i]) % 3))
+}
+
+fn add(a: u8, b: u8) -> u8 {
+    let (a, b) = (digits(a), digits(b));
+    from_digits([0, 1, 2, 3].map(|i| (a[i] + b[i]) % 3))
+}
+
+/// All vectors in the span of `span` and `vector`
+fn extend_span(span: &CardSet, vector: u8) -> CardSet {
+    let mut extended = *span;
+    for v in span.indices() {
+        extended.insert_index(add(v, vector));
+        extended.insert_index(add(add(v, vector), vector));
+    }
+    extended
+}
+
+fn binomial(n: usize, k: usize) -> u128 {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) as u128 / (i + 1) as u128)
+}
+
+/// The table of an orbit that is the same for all tables in it, and the size of the orbit
+struct Canonical {
+    /// The smallest image of the table under a map that sends an affine basis of the table to the unit vectors
+    form: CardSet,
+    /// Number of ordered bases that give the smallest image
+    ties: u128,
+    dimension: usize,
+}
+
+impl Canonical {
+    fn of(table: &CardSet) -> Canonical {
+        let points: Vec<u8> = table.indices().collect();
+        let origin = points[0];
+        let mut span = CardSet::empty();
+        span.insert_index(0);
+        let mut dimension = 0;
+        for &point in &points {
+            let vector = sub(point, origin);
+            if !span.contains_index(vector) {
+                span = extend_span(&span, vector);
+                dimension += 1;
+            }
+        }
+        let mut canonical = Canonical {
+            form: CardSet::empty(),
+            ties: 0,
+            dimension,
+        };
+        for &origin in &points {
+            let mut zero = CardSet::empty();
+            zero.insert_index(0);
+            canonical.search(&points, origin, &mut vec![], zero);
+        }
+        canonical
+    }
+
+    /// Try all ordered bases that start with `basis`
+    fn search(&mut self, points: &[u8], origin: u8, basis: &mut Vec<u8>, span: CardSet) {
+        if basis.len() == self.dimension {
+            let image = points_to_set(points, origin, basis);
+            // Before the first basis, there is no smallest image yet
+            let order = match self.ties {
+                0 => Ordering::Less,
+                _ => image.indices().cmp(self.form.indices()),
+            };
+            match order {
+                Ordering::Less => {
+                    self.form = image;
+                    self.ties = 1;
+                }
+                Ordering::Equal => self.ties += 1,
+                Ordering::Greater => (),
+            }
+            return;
+        }
+        for &point in points {
+            let vector = sub(point, origin);
+            if !span.contains_index(vector) {
+                basis.push(vector);
+                self.search(points, origin, basis, extend_span(&span, vector));
+                basis.pop();
+            }
+        }
+    }
+
+    /// Number of affine maps that map the table onto itself
+    fn stabilizer_order(&self) -> u128 {
+        let free = 4 - self.dimension;
+        // Maps that fix every point of the affine hull of the table
+        let fixing_hull = 3u128.pow((self.dimension * free) as u32) * GL_ORDER[free];
+        self.ties * fixing_hull
+    }
+
+    fn orbit_size(&self) -> u128 {
+        AFFINE_ORDER / self.stabilizer_order()
+    }
+}
+
+/// The points in coordinates of the basis: the vector from `origin` to every point,
+/// written as a combination of the `basis` vectors
+fn points_to_set(points: &[u8], origin: u8, basis: &[u8]) -> CardSet {
+    let mut coordinates = [u8::MAX; CARD_COUNT as usize];
+    for combination in 0..3u8.pow(basis.len() as u32) {
+        let mut vector = 0;
+        let mut image = [0; 4];
+        let mut rest = combination;
+        for (i, &base) in basis.iter().enumerate() {
+            let coefficient = rest % 3;
+            rest /= 3;
+            image[i] = coefficient;
+            for _ in 0..coefficient {
+                vector = add(vector, base);
+            }
+        }
+        coordinates[vector as usize] = from_digits(image);
+    }
+    let mut set = CardSet::empty();
+    for &point in points {
+        set.insert_index(coordinates[sub(point, origin) as usize]);
+    }
+    set
+}
+
+/// Number of sets that a card makes with two cards of the table
+fn new_sets(table: &CardSet, card: u8) -> usize {
+    let made = table.indices().filter(|&other| table.contains_index(COMPLETION[card as usize][other as usize])).count();
+    made / 2
+}
+
+/// Exact numbers of tables of a number of cards, by the number of sets on them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExactCounts {
+    pub cards: usize,
+    /// The number of tables with exactly this many sets, by the number of sets
+    pub counts: Vec<u128>,
+    /// Tables with more sets are not counted
+    pub max_sets: Option<usize>,
+}
+
+impl ExactCounts {
+    /// Number of different tables of this many cards
+    pub fn tables(&self) -> u128 {
+        binomial(CARD_COUNT as usize, self.cards)
+    }
+
+    /// The chance that a table dealt from a shuffled deck has exactly this many sets
+    pub fn probability(&self, sets: usize) -> f64 {
+        self.counts.get(sets).copied().unwrap_or(0) as f64 / self.tables() as f64
+    }
+}
+
+impl fmt::Display for ExactCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Tables of {} cards: {}", self.cards, self.tables())?;
+        writeln!(f, "{:>4}  {:>24}  {:>12}", "Sets", "Tables", "Probability")?;
+        for (sets, count) in self.counts.iter().enumerate() {
+            if *count > 0 {
+                writeln!(f, "{sets:>4}  {count:>24}  {:>12.10}", self.probability(sets))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The largest tables of which all are counted in reasonable time: for 12 cards, each of the 105 orbits of
+/// 8 cards is extended by every 4 of the other 73 cards, about 10^8 tables, and every card more multiplies that by
+/// over 10
+pub const MAX_EXACT_CARDS: usize = 12;
+
+/// Count the tables of `cards` cards by the number of sets on them, exactly, on `threads` threads.
+/// Only tables with at most `max_sets` sets are counted, which is much faster for few sets: a table is not
+/// extended any further once it has too many sets, so counting the tables without a set only visits tables
+/// without a set, of which there are few for any number of cards. Counting all tables of more than
+/// `MAX_EXACT_CARDS` cards does not finish in reasonable time.
+pub fn count_tables(cards: usize, max_sets: Option<usize>, threads: usize) -> ExactCounts {
+    let orbit_cards = cards.saturating_sub(BRUTE_FORCE_CARDS).min(MAX_ORBIT_SIZE);
+    count_tables_from(cards, max_sets, orbit_cards, threads)
+}
+
+/// Add `times` the counts of `other` to `counts`
+fn add_counts(counts: &mut Vec<u128>, other: &[u128], times: u128) {
+    if counts.len() < other.len() {
+        counts.resize(other.len(), 0);
+    }
+    for (count, other) in counts.iter_mut().zip(other) {
+        *count += times * other;
+    }
+}
+
+/// Every table of `cards` cards contains binomial(cards, orbit_cards) smaller tables. Summing the extensions
+/// of all smaller tables counts every table that many times. All smaller tables in an orbit have the same
+/// number of extensions, so only one of every orbit needs to be extended.
+fn count_tables_from(cards: usize, max_sets: Option<usize>, orbit_cards: usize, threads: usize) -> ExactCounts {
+    let max_sets = max_sets.unwrap_or(usize::MAX);
+    let orbits = orbits(orbit_cards, max_sets);
+    let threads = threads.max(1);
+    let mut counts: Vec<u128> = vec![];
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|first| {
+                let orbits = &orbits;
+                scope.spawn(move || {
+                    let mut counts = vec![];
+                    for (table, orbit_size) in orbits.iter().skip(first).step_by(threads) {
+                        let mut extensions = vec![];
+                        let candidates: Vec<u8> = CardSet::full().difference(table).indices().collect();
+                        extend(*table, table.count_sets(), &candidates, cards - orbit_cards, max_sets, &mut extensions);
+                        add_counts(&mut counts, &extensions, *orbit_size);
+                    }
+                    counts
+                })
+            })
+            .collect();
+        for handle in handles {
+            add_counts(&mut counts, &handle.join().expect("A counting thread panicked"), 1);
+        }
+    });
+    if max_sets != usize::MAX && counts.len() <= max_sets {
+        counts.resize(max_sets + 1, 0);
+    }
+    let times_counted = binomial(cards, orbit_cards);
+    for count in &mut counts {
+        debug_assert_eq!(*count % times_counted, 0);
+        *count /= times_counted;
+    }
+    ExactCounts {
+        cards,
+        counts,
+        max_sets: (max_sets != usize::MAX).then_some(max_sets),
+    }
+}
+
+/// One table of every orbit of tables of this many cards with at most `max_sets` sets, with the size of the orbit
+fn orbits(cards: usize, max_sets: usize) -> Vec<(CardSet, u128)> {
+    let mut level = vec![(CardSet::empty(), 1)];
+    for _ in 0..cards {
+        let mut next: HashMap<CardSet, u128> = HashMap::new();
+        for (table, _) in &level {
+            for card in CardSet::full().difference(table).indices() {
+                let mut bigger = *table;
+                bigger.insert_index(card);
+                if bigger.count_sets() > max_sets {
+                    continue;
+                }
+                let canonical = Canonical::of(&bigger);
+                next.entry(canonical.form).or_insert_with(|| canonical.orbit_size());
+            }
+        }
+        level = next.into_iter().collect();
+    }
+    level
+}
+
+/// Count the ways to add `missing` of the candidates to the table, by the number of sets
+fn extend(table: CardSet, sets: usize, candidates: &[u8], missing: usize, max_sets: usize, counts: &mut Vec<u128>) {
+    if missing == 0 {
+        if counts.len() <= sets {
+            counts.resize(sets + 1, 0);
+        }
+        counts[sets] += 1;
+        return;
+    }
+    for (n, &card) in candidates.iter().enumerate() {
+        if candidates.len() - n < missing {
+            return;
+        }
+        let sets = sets + new_sets(&table, card);
+        if sets > max_sets {
+            continue;
+        }
+        let mut bigger = table;
+        bigger.insert_index(card);
+        extend(bigger, sets, &candidates[n + 1..], missing - 1, max_sets, counts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every set is in binomial(78, cards - 3) tables
+    fn check_totals(counts: &ExactCounts) {
+        assert_eq!(counts.counts.iter().sum::<u128>(), counts.tables());
+        let sets: u128 = counts.counts.iter().enumerate().map(|(sets, count)| sets as u128 * count).sum();
+        assert_eq!(sets, 1080 * binomial(78, counts.cards - 3));
+    }
+
+    #[test]
+    fn test_orbit_sizes() {
+        // All pairs are alike, and triples either are a set or not
+        assert_eq!(orbits(1, usize::MAX).len(), 1);
+        assert_eq!(orbits(1, usize::MAX)[0].1, 81);
+        assert_eq!(orbits(2, usize::MAX)[0].1, binomial(81, 2));
+        let mut triples: Vec<u128> = orbits(3, usize::MAX).into_iter().map(|(_, size)| size).collect();
+        triples.sort();
+        assert_eq!(triples, [1080, binomial(81, 3) - 1080]);
+        for cards in 4..=5 {
+            let total: u128 = orbits(cards, usize::MAX).into_iter().map(|(_, size)| size).sum();
+            assert_eq!(total, binomial(81, cards));
+        }
+    }
+
+    #[test]
+    fn test_count_small_tables() {
+        let counts = count_tables_from(4, None, 0, 1);
+        assert_eq!(counts.counts, [binomial(81, 4) - 1080 * 78, 1080 * 78]);
+        check_totals(&counts);
+        assert_eq!(count_tables_from(4, None, 2, 3), counts);
+        assert_eq!(count_tables(4, None, 2), counts);
+
+        let counts = count_tables(6, None, 4);
+        check_totals(&counts);
+        assert_eq!(count_tables_from(6, None, 3, 1), counts);
+    }
+
+    #[test]
+    fn test_count_few_sets() {
+        let counts = count_tables(7, None, 4);
+        check_totals(&counts);
+        let few = count_tables(7, Some(1), 4);
+        assert_eq!(few.counts, counts.counts[..2]);
+        assert_eq!(few.max_sets, Some(1));
+        assert_eq!(few.probability(1), counts.probability(1));
+        // Adding a card to a set makes no new set, so there are 4 cards with exactly 1 set
+        assert_eq!(count_tables(4, Some(0), 1).counts, [binomial(81, 4) - 1080 * 78]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+use crate::{Card, Triple};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    Count,
+    Color,
+    Shading,
+    Shape,
+}
+
+impl Attribute {
+    pub fn iterator() -> std::slice::Iter<'static, Attribute> {
+        static ATTRIBUTES: [Attribute; 4] = [Attribute::Count, Attribute::Color, Attribute::Shading, Attribute::Shape];
+        ATTRIBUTES.iter()
+    }
+
+    /// The value of this attribute of a card, in card notation
+    pub fn of(self, card: &Card) -> char {
+        match self {
+            Attribute::Count => card.count.to_char(),
+            Attribute::Color => card.color.to_char(),
+            Attribute::Shading => card.shading.to_char(),
+            Attribute::Shape => card.shape.to_char(),
+        }
+    }
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Attribute::Count => "count",
+            Attribute::Color => "color",
+            Attribute::Shading => "shading",
+            Attribute::Shape => "shape",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    AllSame,
+    AllDifferent,
+    /// Two cards are the same and the card at this position (0, 1 or 2) differs
+    OddOneOut(usize),
+}
+
+/// How the cards of a triple compare on one attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeReport {
+    pub attribute: Attribute,
+    /// The values of the three cards, in card notation
+    pub values: [char; 3],
+    pub verdict: Verdict,
+}
+
+impl AttributeReport {
+    fn new(attribute: Attribute, triple: &Triple) -> Self {
+        let values = [attribute.of(&triple.0), attribute.of(&triple.1), attribute.of(&triple.2)];
+        let [a, b, c] = values;
+        let verdict = if a == b && b == c {
+            Verdict::AllSame
+        } else if a != b && b != c && c != a {
+            Verdict::AllDifferent
+        } else if a == b {
+            Verdict::OddOneOut(2)
+        } else if a == c {
+            Verdict::OddOneOut(1)
+        } else {
+            Verdict::OddOneOut(0)
+        };
+        AttributeReport {
+            attribute,
+            values,
+            verdict,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        !matches!(self.verdict, Verdict::OddOneOut(_))
+    }
+}
+
+impl fmt::Display for AttributeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.values;
+        match self.verdict {
+            Verdict::AllSame => write!(f, "{}: all the same ({a} {b} {c})", self.attribute),
+            Verdict::AllDifferent => write!(f, "{}: all different ({a} {b} {c})", self.attribute),
+            Verdict::OddOneOut(position) => write!(
+                f,
+                "{}: neither all the same nor all different, card {} is the odd one out ({a} {b} {c})",
+                self.attribute,
+                position + 1
+            ),
+        }
+    }
+}
+
+/// Why three cards are a set or not, attribute by attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Explanation {
+    pub attributes: [AttributeReport; 4],
+}
+
+impl Explanation {
+    /// A set has every attribute all the same or all different
+    pub fn is_set(&self) -> bool {
+        self.attributes.iter().all(AttributeReport::is_ok)
+    }
+
+    /// The attributes that keep the cards from being a set
+    pub fn mismatched(&self) -> impl Iterator<Item = &AttributeReport> {
+        self.attributes.iter().filter(|report| !report.is_ok())
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_set() {
+            writeln!(f, "This is a set:")?;
+        } else {
+            writeln!(f, "This is not a set:")?;
+        }
+        for report in &self.attributes {
+            writeln!(f, "  {report}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Triple {
+    pub fn explain(&self) -> Explanation {
+        Explanation {
+            attributes: [
+                AttributeReport::new(Attribute::Count, self),
+                AttributeReport::new(Attribute::Color, self),
+                AttributeReport::new(Attribute::Shading, self),
+                AttributeReport::new(Attribute::Shape, self),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_explain_set() {
+        let cards = ["1RFd", "2RSo", "3ROs"].map(card);
+        let explanation = Triple::new(cards[0], cards[1], cards[2]).explain();
+        assert!(explanation.is_set());
+        assert_eq!(explanation.mismatched().count(), 0);
+        assert_eq!(explanation.attributes[0].verdict, Verdict::AllDifferent);
+        assert_eq!(explanation.attributes[1].verdict, Verdict::AllSame);
+        assert_eq!(explanation.attributes[1].values, ['R', 'R', 'R']);
+    }
+
+    #[test]
+    fn test_explain_odd_one_out() {
+        let cards = ["1RFd", "2GFo", "3RFs"].map(card);
+        let explanation = Triple::new(cards[0], cards[1], cards[2]).explain();
+        assert!(!explanation.is_set());
+        let mismatched: Vec<_> = explanation.mismatched().collect();
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].attribute, Attribute::Color);
+        assert_eq!(mismatched[0].verdict, Verdict::OddOneOut(1));
+        assert_eq!(
+            mismatched[0].to_string(),
+            "color: neither all the same nor all different, card 2 is the odd one out (R G R)"
+        );
+    }
+
+    #[test]
+    fn test_explain_agrees_with_is_set() {
+        let cards = crate::generate_all_cards();
+        for a in &cards[..20] {
+            for b in &cards[20..40] {
+                for c in &cards[40..] {
+                    let triple = Triple::new(*a, *b, *c);
+                    assert_eq!(triple.explain().is_set(), triple.is_set());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_display_explanation() {
+        let cards = ["1RFd", "1GFd", "1GFd"].map(card);
+        let text = Triple::new(cards[0], cards[1], cards[2]).explain().to_string();
+        assert_eq!(
+            text,
+            "This is not a set:
+  count: all the same (1 1 1)
+  color: neither all the same nor all different, card 1 is the odd one out (R G G)
+  shading: all the same (F F F)
+  shape: all the same (d d d)
+"
+        );
+    }
+}
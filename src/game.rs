@@ -0,0 +1,352 @@
+use std::error;
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{find_set, generate_all_cards, Card, Error, Triple};
+
+/// Number of cards on the table, unless there is no set among them
+pub const TABLE_SIZE: usize = 12;
+
+/// The cards that have not been dealt yet, dealt from the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    /// All 81 cards, in the order of `generate_all_cards`
+    pub fn new() -> Self {
+        Deck::from_cards(generate_all_cards())
+    }
+
+    /// All 81 cards, shuffled
+    pub fn shuffled<R: Rng>(rng: &mut R) -> Self {
+        let mut deck = Deck::new();
+        deck.cards.shuffle(rng);
+        deck
+    }
+
+    /// A deck of the given cards, the first card on top
+    pub fn from_cards(cards: Vec<Card>) -> Self {
+        Deck { cards }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Take up to `count` cards from the top of the deck
+    pub fn deal(&mut self, count: usize) -> Vec<Card> {
+        self.cards.drain(..count.min(self.cards.len())).collect()
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Self {
+        Deck::new()
+    }
+}
+
+/// Why a claimed set was not accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimError {
+    NotOnTable(Card),
+    SameCard(Card),
+    NotASet,
+    /// Claimed that there is no set, while there is one
+    SetOnTable,
+    /// Rightly claimed that there is no set, but there are no cards left to deal
+    GameOver,
+}
+
+impl fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClaimError::NotOnTable(card) => write!(f, "{} is not on the table", card.notation()),
+            ClaimError::SameCard(card) => write!(f, "{} is claimed more than once", card.notation()),
+            ClaimError::NotASet => write!(f, "these cards do not form a set"),
+            ClaimError::SetOnTable => write!(f, "there is a set on the table"),
+            ClaimError::GameOver => write!(f, "there is no set and the deck is empty, the game is over"),
+        }
+    }
+}
+
+impl error::Error for ClaimError {}
+
+/// A game of Set: cards are dealt from the deck to the table, and sets are taken from it
+#[derive(Debug, Clone)]
+pub struct Game {
+    deck: Deck,
+    table: Vec<Card>,
+    taken: Vec<[Card; 3]>,
+    /// Deal 3 more cards as soon as there is no set, instead of waiting for `claim_no_set`
+    auto_extra: bool,
+}
+
+impl Game {
+    /// A game with a randomly shuffled deck
+    pub fn new() -> Self {
+        Game::from_deck(Deck::shuffled(&mut thread_rng()))
+    }
+
+    /// A game that is dealt the same way for the same seed
+    pub fn with_seed(seed: u64) -> Self {
+        Game::from_deck(Deck::shuffled(&mut ChaCha8Rng::seed_from_u64(seed)))
+    }
+
+    /// Start a game by dealing 12 cards from the deck, and more when there is no set among them
+    pub fn from_deck(mut deck: Deck) -> Self {
+        let table = deck.deal(TABLE_SIZE);
+        let mut game = Game {
+            deck,
+            table,
+            taken: vec![],
+            auto_extra: true,
+        };
+        game.deal_until_set();
+        game
+    }
+
+    /// Only deal extra cards when a player claims there is no set, as when playing by hand
+    pub fn without_auto_extra(mut deck: Deck) -> Self {
+        let table = deck.deal(TABLE_SIZE);
+        Game {
+            deck,
+            table,
+            taken: vec![],
+            auto_extra: false,
+        }
+    }
+
+    pub fn table(&self) -> &[Card] {
+        &self.table
+    }
+
+    pub fn deck(&self) -> &Deck {
+        &self.deck
+    }
+
+    /// The sets taken so far, in the order they were taken
+    pub fn taken(&self) -> &[[Card; 3]] {
+        &self.taken
+    }
+
+    pub fn find_set(&self) -> Result<Triple, Error> {
+        find_set(&self.table)
+    }
+
+    /// The game is over when the deck is empty and there is no set left on the table
+    pub fn is_over(&self) -> bool {
+        self.deck.is_empty() && self.find_set().is_err()
+    }
+
+    /// Take a set from the table. The cards are replaced from the deck when that brings
+    /// the table back to 12 cards, and 3 more are dealt as long as there is no set.
+    pub fn claim(&mut self, cards: [Card; 3]) -> Result<(), ClaimError> {
+        let mut positions = [0; 3];
+        for (i, card) in cards.iter().enumerate() {
+            if cards[..i].contains(card) {
+                return Err(ClaimError::SameCard(*card));
+            }
+            positions[i] = self
+                .table
+                .iter()
+                .position(|on_table| on_table == card)
+                .ok_or(ClaimError::NotOnTable(*card))?;
+        }
+        if !Triple::new(cards[0], cards[1], cards[2]).is_set() {
+            return Err(ClaimError::NotASet);
+        }
+
+        // Replace the cards in place, so the other cards stay where they are
+        if self.table.len() <= TABLE_SIZE && self.deck.len() >= 3 {
+            for (position, card) in positions.into_iter().zip(self.deck.deal(3)) {
+                self.table[position] = card;
+            }
+        } else {
+            self.table.retain(|card| !cards.contains(card));
+        }
+        self.taken.push(cards);
+        self.deal_until_set();
+        Ok(())
+    }
+
+    /// Deal 3 extra cards, when there is indeed no set on the table.
+    /// With an empty deck nothing can be dealt, and the game is over.
+    pub fn claim_no_set(&mut self) -> Result<(), ClaimError> {
+        if self.find_set().is_ok() {
+            return Err(ClaimError::SetOnTable);
+        }
+        if self.deck.is_empty() {
+            return Err(ClaimError::GameOver);
+        }
+        self.table.extend(self.deck.deal(3));
+        Ok(())
+    }
+
+    fn deal_until_set(&mut self) {
+        while self.table.len() < TABLE_SIZE && !self.deck.is_empty() {
+            let missing = TABLE_SIZE - self.table.len();
+            self.table.extend(self.deck.deal(missing));
+        }
+        while self.auto_extra && self.find_set().is_err() && !self.deck.is_empty() {
+            self.table.extend(self.deck.deal(3));
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Count, Shading, Shape};
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    // 16 cards that only use two of the values of every attribute, so no three of them form a set
+    fn without_sets() -> Vec<Card> {
+        generate_all_cards()
+            .into_iter()
+            .filter(|card| card.color != Color::Purple)
+            .filter(|card| card.count != Count::Three)
+            .filter(|card| card.shading != Shading::Striped)
+            .filter(|card| card.shape != Shape::Squiggle)
+            .collect()
+    }
+
+    #[test]
+    fn test_deck() {
+        let mut deck = Deck::new();
+        assert_eq!(deck.len(), 81);
+        assert_eq!(deck.deal(12).len(), 12);
+        assert_eq!(deck.len(), 69);
+        assert_eq!(deck.deal(100).len(), 69);
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn test_seeded_games_are_equal() {
+        assert_eq!(Game::with_seed(42).table(), Game::with_seed(42).table());
+        assert_ne!(Game::with_seed(42).table(), Game::with_seed(43).table());
+    }
+
+    #[test]
+    fn test_new_game() {
+        let game = Game::with_seed(1);
+        assert!(game.table().len() >= TABLE_SIZE);
+        assert_eq!(game.table().len() + game.deck().len(), 81);
+        assert!(game.find_set().is_ok());
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn test_claim_set() {
+        let mut game = Game::with_seed(1);
+        let table_before = game.table().to_vec();
+        let set = game.find_set().unwrap().cards();
+        game.claim(set).unwrap();
+
+        assert_eq!(game.taken(), [set]);
+        assert_eq!(game.table().len(), table_before.len());
+        assert!(set.iter().all(|card| !game.table().contains(card)));
+        // The other cards stay in their place
+        for (before, after) in table_before.iter().zip(game.table()) {
+            assert!(set.contains(before) || before == after);
+        }
+    }
+
+    #[test]
+    fn test_claim_errors() {
+        let mut game = Game::from_deck(Deck::from_cards(["1RFd", "1RFo", "1RFs", "2GOd"].map(card).to_vec()));
+        assert_eq!(game.claim(["1RFd", "1RFo", "2GOd"].map(card)), Err(ClaimError::NotASet));
+        assert_eq!(
+            game.claim(["1RFd", "1RFo", "3PSs"].map(card)),
+            Err(ClaimError::NotOnTable(card("3PSs")))
+        );
+        assert_eq!(
+            game.claim(["1RFd", "1RFd", "1RFo"].map(card)),
+            Err(ClaimError::SameCard(card("1RFd")))
+        );
+        assert_eq!(game.table().len(), 4);
+
+        game.claim(["1RFd", "1RFo", "1RFs"].map(card)).unwrap();
+        assert_eq!(game.table(), [card("2GOd")]);
+        assert!(game.is_over());
+    }
+
+    #[test]
+    fn test_deal_extra_cards_without_set() {
+        let mut cards = without_sets();
+        cards.truncate(TABLE_SIZE);
+        let rest: Vec<Card> = generate_all_cards().into_iter().filter(|card| !cards.contains(card)).collect();
+        cards.extend(rest);
+
+        let mut game = Game::from_deck(Deck::from_cards(cards));
+        assert_eq!(game.table().len(), 15);
+        assert!(game.find_set().is_ok());
+
+        // Taking a set from 15 cards does not refill the table
+        let set = game.find_set().unwrap().cards();
+        game.claim(set).unwrap();
+        assert!(game.table().len() >= TABLE_SIZE);
+        assert!(game.deck().len() <= 81 - 15);
+        assert_eq!(game.table().len() + game.deck().len(), 81 - 3);
+    }
+
+    #[test]
+    fn test_claim_no_set() {
+        let mut cards = without_sets();
+        cards.truncate(TABLE_SIZE);
+        cards.extend(["1PSs", "2PSs", "3PSs"].map(card));
+
+        let mut game = Game::without_auto_extra(Deck::from_cards(cards));
+        assert_eq!(game.table().len(), TABLE_SIZE);
+        assert!(game.find_set().is_err());
+        assert!(!game.is_over());
+
+        game.claim_no_set().unwrap();
+        assert_eq!(game.table().len(), 15);
+        assert_eq!(game.claim_no_set(), Err(ClaimError::SetOnTable));
+    }
+
+    #[test]
+    fn test_claim_no_set_with_empty_deck() {
+        let mut game = Game::without_auto_extra(Deck::from_cards(without_sets()[..TABLE_SIZE].to_vec()));
+        assert!(game.deck().is_empty());
+        assert!(game.is_over());
+        assert_eq!(game.claim_no_set(), Err(ClaimError::GameOver));
+        assert_eq!(game.table().len(), TABLE_SIZE);
+
+        // A wrong claim is wrong, also at the end of the game
+        let mut cards = without_sets()[..TABLE_SIZE - 3].to_vec();
+        cards.extend(["1PSs", "2PSs", "3PSs"].map(card));
+        let mut game = Game::without_auto_extra(Deck::from_cards(cards));
+        assert!(game.deck().is_empty());
+        assert_eq!(game.claim_no_set(), Err(ClaimError::SetOnTable));
+    }
+
+    #[test]
+    fn test_play_until_over() {
+        let mut game = Game::with_seed(7);
+        while !game.is_over() {
+            let set = game.find_set().unwrap().cards();
+            game.claim(set).unwrap();
+        }
+        assert!(game.deck().is_empty());
+        assert_eq!(game.taken().len() * 3 + game.table().len(), 81);
+    }
+}
@@ -1,10 +1,43 @@
+use std::collections::HashMap;
 use std::{fmt, vec};
 
 extern crate ansi_colors;
 use ansi_colors::*;
 use std::slice::Iter;
+use cardset::COMPLETION;
+
+pub mod cap;
+pub mod cardset;
+pub mod error;
+pub mod exact;
+pub mod explain;
+pub mod game;
+pub mod notation;
+pub mod puzzle;
+pub mod rules;
+pub mod simulate;
+pub mod symmetry;
+pub mod tree;
+pub mod variant;
+pub mod vision;
+pub use cap::{largest_cap, random_maximal_cap, MAX_CAP_SIZE};
+pub use cardset::CardSet;
+pub use error::Error;
+pub use exact::{count_tables, ExactCounts, MAX_EXACT_CARDS};
+pub use explain::{Attribute, AttributeReport, Explanation, Verdict};
+pub use game::{ClaimError, Deck, Game};
+pub use notation::{parse_table, ParseCardError, ParseTableError};
+pub use puzzle::{difficulty, Overlap, Puzzle, PuzzleGenerator};
+pub use rules::{find_all_super_set_positions, find_all_super_sets, Found, Rules, SuperSet};
+pub use simulate::{Histogram, Policy, Simulation, Statistics};
+pub use symmetry::{are_equivalent, canonical_hash, canonical_table, Relabeling};
+pub use variant::{SetVariant, VariantCard};
+
+const COLORS: [Color; 3] = [Color::Red, Color::Green, Color::Purple];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Color {
     Red,
     Green,
@@ -13,40 +46,46 @@ pub enum Color {
 
 impl Color {
     pub fn iterator() -> Iter<'static, Color> {
-        static COLORS: [Color; 3] = [Color::Red, Color::Green, Color::Purple];
         COLORS.iter()
     }
 }
 
+const COUNTS: [Count; 3] = [Count::One, Count::Two, Count::Three];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Count {
     One,
     Two,
     Three,
 }
 
-impl Count {
-    #[allow(dead_code)]
-    const fn from_int(count: u8) -> Self {
+impl TryFrom<u8> for Count {
+    type Error = Error;
+
+    fn try_from(count: u8) -> Result<Self, Error> {
         match count {
-            1 => Count::One,
-            2 => Count::Two,
-            3 => Count::Three,
-            _ => {
-                panic!("Cannot handle counts other than 1,2,3")
-            }
+            1 => Ok(Count::One),
+            2 => Ok(Count::Two),
+            3 => Ok(Count::Three),
+            _ => Err(Error::InvalidAttribute {
+                attribute: Attribute::Count,
+                value: count.to_string(),
+            }),
         }
     }
+}
 
+impl Count {
     pub fn iterator() -> Iter<'static, Count> {
-        static COUNTS: [Count; 3] = [Count::One, Count::Two, Count::Three];
         COUNTS.iter()
     }
 }
 
-impl Into<usize> for Count {
-    fn into(self) -> usize {
-        match self {
+impl From<Count> for usize {
+    fn from(count: Count) -> usize {
+        match count {
             Count::One => 1,
             Count::Two => 2,
             Count::Three => 3,
@@ -54,7 +93,11 @@ impl Into<usize> for Count {
     }
 }
 
+const SHADINGS: [Shading; 3] = [Shading::Open, Shading::Solid, Shading::Striped];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Shading {
     Open,
     Solid,
@@ -63,12 +106,15 @@ pub enum Shading {
 
 impl Shading {
     pub fn iterator() -> Iter<'static, Shading> {
-        static SHADINGS: [Shading; 3] = [Shading::Open, Shading::Solid, Shading::Striped];
         SHADINGS.iter()
     }
 }
 
+const SHAPES: [Shape; 3] = [Shape::Diamond, Shape::Oval, Shape::Squiggle];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Shape {
     Diamond,
     Oval,
@@ -89,12 +135,12 @@ impl fmt::Display for Shape {
 
 impl Shape {
     pub fn iterator() -> Iter<'static, Shape> {
-        static SHAPES: [Shape; 3] = [Shape::Diamond, Shape::Oval, Shape::Squiggle];
         SHAPES.iter()
     }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Card {
     color: Color,
     count: Count,
@@ -102,6 +148,23 @@ pub struct Card {
     shape: Shape,
 }
 
+impl Card {
+    pub fn new(color: Color, count: Count, shading: Shading, shape: Shape) -> Self {
+        Card {
+            color,
+            count,
+            shading,
+            shape,
+        }
+    }
+
+    /// The only card that forms a set with this card and `other`
+    pub fn complete_set(&self, other: &Card) -> Card {
+        let index = COMPLETION[self.index() as usize][other.index() as usize];
+        Card::from_index(index).expect("The completion of two cards is a card")
+    }
+}
+
 impl fmt::Display for Card {
     // This trait requires `fmt` with this exact signature.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -158,6 +221,12 @@ pub struct HighlightedCard<'a> {
     card: &'a Card,
 }
 
+impl<'a> HighlightedCard<'a> {
+    pub fn new(card: &'a Card) -> Self {
+        HighlightedCard { card }
+    }
+}
+
 impl fmt::Display for HighlightedCard<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let shape_chr = format!("{}", self.card.shape).repeat(self.card.count.into());        
@@ -215,10 +284,24 @@ impl fmt::Display for HighlightedCard<'_> {
     }
 }
 
-#[derive(Debug)]
-pub struct Triple<'a>(&'a Card, &'a Card, &'a Card);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triple(Card, Card, Card);
+
+impl Triple {
+    pub fn new(first: Card, second: Card, third: Card) -> Self {
+        Triple(first, second, third)
+    }
+
+    /// The cards at three positions of a table
+    pub fn at(cards: &[Card], positions: [usize; 3]) -> Self {
+        Triple(cards[positions[0]], cards[positions[1]], cards[positions[2]])
+    }
+
+    pub fn cards(&self) -> [Card; 3] {
+        [self.0, self.1, self.2]
+    }
 
-impl Triple<'_> {
     fn all_different_color(&self) -> bool {
         self.0.color != self.1.color && self.1.color != self.2.color && self.2.color != self.0.color
     }
@@ -251,7 +334,8 @@ impl Triple<'_> {
         self.0.shading == self.1.shading && self.1.shading == self.2.shading && self.2.shading == self.0.shading
     }
 
-    fn is_set(&self) -> bool {
+    /// The classic rules, without going through `SetVariant`, as the solver checks many triples
+    pub fn is_set(&self) -> bool {
         let color_same_or_diff = self.all_same_color() || self.all_different_color();
         let shape_same_or_diff = self.all_same_shape() || self.all_different_shape();
         let count_same_or_diff = self.all_same_count() || self.all_different_count();
@@ -261,95 +345,173 @@ impl Triple<'_> {
     }
 }
 
-#[derive(Debug)]
-pub struct Table<'a> {
-    pub cards: Vec<&'a Card>,
-    pub triples: Vec<Triple<'a>>,
+/// Cards on the table, and sets among them as positions in `cards`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TableData"))]
+pub struct Table {
+    cards: Vec<Card>,
+    triples: Vec<[usize; 3]>,
 }
 
-impl Table<'_> {
-    
+/// A table as it is read, before its sets are checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TableData {
+    cards: Vec<Card>,
+    triples: Vec<[usize; 3]>,
 }
 
-#[derive(Debug, Clone)]
-pub struct SetError;
-
-pub fn find_set(cards: Vec<&Card>) -> Result<Triple, SetError> {
-    for subset in combinations::Combinations::new(cards, 3) {
-        let triple = Triple(
-            subset[0],
-            subset[1], 
-            subset[2],
-        );
-        if triple.is_set() {
-            return Ok(triple);
+#[cfg(feature = "serde")]
+impl TryFrom<TableData> for Table {
+    type Error = Error;
+
+    /// Only sets at increasing positions of the cards, as `Table::solve` finds them
+    fn try_from(data: TableData) -> Result<Self, Self::Error> {
+        for &positions in &data.triples {
+            let [a, b, c] = positions;
+            let increasing = a < b && b < c && c < data.cards.len();
+            if !increasing || !Triple::at(&data.cards, positions).is_set() {
+                return Err(Error::MalformedTable(format!(
+                    "{positions:?} is not a set among the {} cards",
+                    data.cards.len()
+                )));
+            }
         }
+        Ok(Table { cards: data.cards, triples: data.triples })
     }
-    return Err(SetError);
 }
 
-pub fn find_all_sets(cards: Vec<&Card>) -> Vec<Triple> {
-    let mut sets: Vec<Triple> = vec![];
-    for subset in combinations::Combinations::new(cards, 3) {
-        let triple = Triple(
-            subset[0],
-            subset[1],
-            subset[2],
-        );
-        if triple.is_set() {
-            sets.push(triple);
+impl Table {
+    /// The cards with all the sets among them
+    pub fn solve(cards: Vec<Card>) -> Self {
+        let triples = find_all_set_positions(&cards);
+        Table { cards, triples }
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// The positions of the cards of every set
+    pub fn triples(&self) -> &[[usize; 3]] {
+        &self.triples
+    }
+
+    /// The cards of each set
+    pub fn sets(&self) -> impl Iterator<Item = Triple> + '_ {
+        self.triples.iter().map(|&positions| Triple::at(&self.cards, positions))
+    }
+}
+
+/// Positions of all sets among some cards, in table order: the positions of every set are sorted, and so
+/// are the sets. Every pair of cards is completed to a set by exactly one card, which is looked up instead
+/// of trying all triples.
+struct Sets<'a> {
+    cards: &'a [Card],
+    // Positions of every card, more than one when a card is on the table twice
+    positions: HashMap<Card, Vec<usize>>,
+    // The next pair to complete, as positions
+    i: usize,
+    j: usize,
+    // Sets found for the last pair that were not returned yet, the first one last
+    pending: Vec<[usize; 3]>,
+}
+
+impl<'a> Sets<'a> {
+    fn new(cards: &'a [Card]) -> Self {
+        let mut positions: HashMap<Card, Vec<usize>> = HashMap::new();
+        for (position, &card) in cards.iter().enumerate() {
+            positions.entry(card).or_default().push(position);
         }
+        Sets { cards, positions, i: 0, j: 1, pending: vec![] }
     }
-    sets
 }
 
-pub fn generate_all_cards() -> Vec<Card> {
-    let mut all_cards: Vec<Card> = vec![];
-    for _color in Color::iterator() {
-        for _count in Count::iterator() {
-            for _shading in Shading::iterator() {
-                for _shape in Shape::iterator() {
-                    let card = Card {
-                        color: *_color,
-                        count: *_count,
-                        shading: *_shading,
-                        shape: *_shape,
-                    };
-                    all_cards.push(card);
-                }
+impl Iterator for Sets<'_> {
+    type Item = [usize; 3];
+
+    fn next(&mut self) -> Option<[usize; 3]> {
+        while self.pending.is_empty() && self.i + 2 < self.cards.len() {
+            let (i, j) = (self.i, self.j);
+            if j + 1 < self.cards.len() {
+                self.j += 1;
+            } else {
+                self.i += 1;
+                self.j = self.i + 1;
+            }
+            // Only take the third card after the pair, so every set is found once
+            let third = self.cards[i].complete_set(&self.cards[j]);
+            if let Some(positions) = self.positions.get(&third) {
+                let sets = positions.iter().rev().filter(|&&k| k > j);
+                self.pending.extend(sets.map(|&k| [i, j, k]));
             }
         }
+        self.pending.pop()
     }
-    all_cards
 }
 
-impl fmt::Display for Table<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            // A table should always have a multiple of 3 cards
-        let remainder = self.cards.len() % 3;
-        assert!(remainder == 0);
-        let row_length = self.cards.len() / 3;
+pub fn find_set(cards: &[Card]) -> Result<Triple, Error> {
+    Sets::new(cards).next().map(|positions| Triple::at(cards, positions)).ok_or(Error::NoSet)
+}
+
+pub fn find_all_sets(cards: &[Card]) -> Vec<Triple> {
+    Sets::new(cards).map(|positions| Triple::at(cards, positions)).collect()
+}
+
+/// Positions of the cards of every set, so equal cards at different positions are told apart
+pub fn find_all_set_positions(cards: &[Card]) -> Vec<[usize; 3]> {
+    Sets::new(cards).collect()
+}
 
-        // let self_iter = self.cards.iter();
+/// Every card once, in the order of `Card::index`
+pub(crate) static ALL_CARDS: [Card; 81] = all_cards();
+
+const fn all_cards() -> [Card; 81] {
+    let mut cards = [Card { color: Color::Red, count: Count::One, shading: Shading::Open, shape: Shape::Diamond }; 81];
+    let mut index = 0;
+    while index < 81 {
+        let [color, count, shading, shape] = cardset::digits(index as u8);
+        cards[index] = Card {
+            color: COLORS[color as usize],
+            count: COUNTS[count as usize],
+            shading: SHADINGS[shading as usize],
+            shape: SHAPES[shape as usize],
+        };
+        index += 1;
+    }
+    cards
+}
+
+pub fn generate_all_cards() -> Vec<Card> {
+    ALL_CARDS.to_vec()
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The cards are shown in 3 rows, the last row is shorter when the cards do not fit evenly
+        let row_length = self.cards.len().div_ceil(3);
 
         for triple in &self.triples {
             for x in 0..3 {
-                for y in 0..row_length{
+                for y in 0..row_length {
                     let index = (x * row_length) + y;
-                    let card = self.cards[index];
-                    if card == triple.0 || card == triple.1 || card == triple.2
+                    let Some(card) = self.cards.get(index) else {
+                        break;
+                    };
+                    if triple.contains(&index)
                     {
-                        write!(f, "{}", HighlightedCard{card: card}).unwrap();
+                        write!(f, "{}", HighlightedCard{card})?;
                     }
                     else {
-                        write!(f, "{}", card).unwrap();
+                        write!(f, "{}", card)?;
                     }
                 }
-                write!(f, "\n").unwrap();
+                writeln!(f)?;
             }
-            write!(f, "--------------------\n").unwrap();
+            writeln!(f, "--------------------")?;
         }
-        write!(f, "")
+        Ok(())
     }
 }
 
@@ -362,7 +524,7 @@ mod tests {
     // c1,2,3 together form a set, c1,2,4 do not
     const C1: Card = Card {
         color: Color::Green,
-        count: Count::from_int(1),
+        count: Count::One,
         shading: Shading::Solid,
         shape: Shape::Squiggle,
     };
@@ -370,106 +532,106 @@ mod tests {
         color: Color::Green,
         shape: Shape::Oval,
         shading: Shading::Open,
-        count: Count::from_int(2),
+        count: Count::Two,
     };
     const C3: Card = Card {
         color: Color::Green,
         shape: Shape::Diamond,
         shading: Shading::Striped,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const C4: Card = Card {
         color: Color::Green,
         shape: Shape::Diamond,
         shading: Shading::Solid,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const K1: Card = Card {
         color: Color::Purple,
         shape: Shape::Squiggle,
         shading: Shading::Solid,
-        count: Count::from_int(1),
+        count: Count::One,
     };
     const K2: Card = Card {
         color: Color::Purple,
         shape: Shape::Squiggle,
         shading: Shading::Striped,
-        count: Count::from_int(2),
+        count: Count::Two,
     };
     const K3: Card = Card {
         color: Color::Purple,
         shape: Shape::Oval,
         shading: Shading::Open,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const K4: Card = Card {
         color: Color::Green,
         shape: Shape::Squiggle,
         shading: Shading::Open,
-        count: Count::from_int(1),
+        count: Count::One,
     };
     const K5: Card = Card {
         color: Color::Purple,
         shape: Shape::Squiggle,
         shading: Shading::Open,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const K6: Card = Card {
         color: Color::Green,
         shape: Shape::Diamond,
         shading: Shading::Solid,
-        count: Count::from_int(2),
+        count: Count::Two,
     };
     const K7: Card = Card {
         color: Color::Purple,
         shape: Shape::Diamond,
         shading: Shading::Solid,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const K8: Card = Card {
         color: Color::Red,
         shape: Shape::Oval,
         shading: Shading::Open,
-        count: Count::from_int(1),
+        count: Count::One,
     };
     const K9: Card = Card {
         color: Color::Red,
         shape: Shape::Oval,
         shading: Shading::Open,
-        count: Count::from_int(2),
+        count: Count::Two,
     };
     const K10: Card = Card {
         color: Color::Red,
         shape: Shape::Diamond,
         shading: Shading::Open,
-        count: Count::from_int(3),
+        count: Count::Three,
     };
     const K11: Card = Card {
         color: Color::Green,
         shape: Shape::Oval,
         shading: Shading::Open,
-        count: Count::from_int(2),
+        count: Count::Two,
     };
     const K12: Card = Card {
         color: Color::Purple,
         shape: Shape::Diamond,
         shading: Shading::Solid,
-        count: Count::from_int(1),
+        count: Count::One,
     };
 
     #[test]
     fn test_cards_equal() {
         let a = Card {
             color: Color::Green,
-            count: Count::from_int(1),
+            count: Count::One,
             shading: Shading::Solid,
             shape: Shape::Squiggle,
         };
-        let b = a.clone();
+        let b = a;
 
         let c = Card {
             color: Color::Green,
-            count: Count::from_int(1),
+            count: Count::One,
             shading: Shading::Solid,
             shape: Shape::Squiggle,
         };
@@ -483,15 +645,15 @@ mod tests {
     fn test_hashset_keeps_only_unique() {
         let a = Card {
             color: Color::Green,
-            count: Count::from_int(1),
+            count: Count::One,
             shading: Shading::Solid,
             shape: Shape::Squiggle,
         };
-        let b = a.clone();
+        let b = a;
 
         let c = Card {
             color: Color::Green,
-            count: Count::from_int(1),
+            count: Count::One,
             shading: Shading::Solid,
             shape: Shape::Squiggle,
         };
@@ -505,13 +667,13 @@ mod tests {
         set.insert(c);
         assert_eq!(set.len(), 1);
 
-        let collection = vec![a, b, c];
+        let collection = [a, b, c];
         assert_eq!(collection.len(), 3);
         let mut set2 = HashSet::new();
         set2.extend(collection);
         assert_eq!(set2.len(), 1);
 
-        let collection = vec![&a, &b, &c];
+        let collection = [&a, &b, &c];
         assert_eq!(collection.len(), 3);
         let mut set3: HashSet<&Card> = HashSet::new();
         set3.extend(collection.iter());
@@ -520,37 +682,142 @@ mod tests {
 
     #[test]
     fn test_same_color_true() {
-        assert_eq!(Triple(&K1, &K2, &K3).all_same_color(), true);
+        assert!(Triple(K1, K2, K3).all_same_color());
     }
 
     #[test]
     fn test_different_color_false() {
-        assert_eq!(Triple(&K1, &K2, &K3).all_different_color(), false);
+        assert!(!Triple(K1, K2, K3).all_different_color());
     }
 
     #[test]
     fn test_same_color_false() {
-        assert_eq!(Triple(&K6, &K7, &K8).all_same_color(), false);
+        assert!(!Triple(K6, K7, K8).all_same_color());
     }
 
     #[test]
     fn test_different_color_true() {
-        assert_eq!(Triple(&K6, &K7, &K8).all_different_color(), true);
+        assert!(Triple(K6, K7, K8).all_different_color());
     }
 
     #[test]
     fn test_find_all_sets_1() {
-        let all_cards: Vec<&Card> = vec![
-            &C1, &C2, &C3, &C4, &K1, &K2, &K3, &K4, &K5, &K6, &K7, &K8, &K9, &K10, &K11, &K12,
+        let all_cards: Vec<Card> = vec![
+            C1, C2, C3, C4, K1, K2, K3, K4, K5, K6, K7, K8, K9, K10, K11, K12,
         ];
 
-        let set = find_set(all_cards);
+        let set = find_set(&all_cards);
         println!("Found a set: {:#?}", set);
     }
 
+    #[test]
+    fn test_find_set_in_few_cards() {
+        assert!(find_set(&[C1, C2]).is_err());
+        assert!(find_set(&[C1, C2, C3]).is_ok());
+        assert_eq!(find_all_sets(&[C1, C2, C3]).len(), 1);
+    }
+
+    #[test]
+    fn test_complete_set() {
+        assert_eq!(C1.complete_set(&C2), C3);
+        assert_eq!(C2.complete_set(&C1), C3);
+        assert_eq!(C1.complete_set(&C1), C1);
+        for a in generate_all_cards() {
+            for b in generate_all_cards() {
+                let c = a.complete_set(&b);
+                assert!(Triple(a, b, c).is_set());
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_all_sets_in_deck() {
+        let cards = generate_all_cards();
+        let sets = find_all_sets(&cards);
+        // Every pair of the 81 cards is in exactly one set
+        assert_eq!(sets.len(), 81 * 80 / 6);
+        assert!(sets.iter().all(|set| set.is_set() && set.0 < set.1 && set.1 < set.2));
+
+        let mut brute_force = 0;
+        for (i, a) in cards.iter().enumerate() {
+            for (j, b) in cards.iter().enumerate().skip(i + 1) {
+                brute_force += cards[j + 1..].iter().filter(|c| Triple(*a, *b, **c).is_set()).count();
+            }
+        }
+        assert_eq!(sets.len(), brute_force);
+    }
+
+    #[test]
+    fn test_table_positions() {
+        // A table can be returned, it owns its cards
+        fn solved() -> Table {
+            Table::solve(vec![C2, C3, C1])
+        }
+        let table = solved();
+        assert_eq!(table.triples(), [[0, 1, 2]]);
+        assert_eq!(table.sets().collect::<Vec<_>>(), [Triple(C2, C3, C1)]);
+        assert_eq!(find_all_set_positions(&[C4, C1, C2, C3]), [[1, 2, 3]]);
+
+        // The same card twice gives two sets, told apart by position
+        let triples = find_all_set_positions(&[C1, C2, C3, C3]);
+        assert_eq!(triples.len(), 2);
+        assert!(triples.contains(&[0, 1, 2]));
+        assert!(triples.contains(&[0, 1, 3]));
+    }
+
+    #[test]
+    fn test_sets_in_table_order() {
+        // Cards that are not in the order of their encoding
+        let cards = [K12, C3, K5, C1, K9, C2, K1, C4, K7, K3, K11, K6];
+        let triples = find_all_set_positions(&cards);
+        let mut brute_force = vec![];
+        for i in 0..cards.len() {
+            for j in i + 1..cards.len() {
+                for k in j + 1..cards.len() {
+                    if Triple(cards[i], cards[j], cards[k]).is_set() {
+                        brute_force.push([i, j, k]);
+                    }
+                }
+            }
+        }
+        assert!(triples.contains(&[1, 3, 5]));
+        assert_eq!(triples, brute_force);
+        assert_eq!(find_set(&cards).unwrap(), Triple::at(&cards, brute_force[0]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let table = Table::solve(vec![C2, C3, C1]);
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.starts_with(r#"{"cards":[{"color":"#));
+        assert_eq!(serde_json::from_str::<Table>(&json).unwrap(), table);
+        // Positions past the cards, or of cards that are no set, are refused instead of panicking in `sets`
+        let beyond = json.replace("[[0,1,2]]", "[[0,1,3]]");
+        assert!(serde_json::from_str::<Table>(&beyond).is_err());
+        let twice = json.replace("[[0,1,2]]", "[[0,0,0]]");
+        assert!(serde_json::from_str::<Table>(&twice).is_err());
+
+        let triple = Triple(C1, C2, C3);
+        let json = serde_json::to_string(&triple).unwrap();
+        assert_eq!(serde_json::from_str::<Triple>(&json).unwrap(), triple);
+        assert_eq!(serde_json::to_string(&Count::Two).unwrap(), r#""two""#);
+    }
+
+    #[test]
+    fn test_malformed_table() {
+        // Cards that do not fill 3 rows are still solved, for a photo in which not every card was recognised
+        let table = Table::solve(vec![C4, C1, C2, C3]);
+        assert_eq!(table.triples(), [[1, 2, 3]]);
+        assert_eq!(table.to_string().lines().count(), 4);
+        assert!(matches!(find_set(&[C1, C2, C4]), Err(Error::NoSet)));
+        assert_eq!(Count::try_from(2).unwrap(), Count::Two);
+        assert!(Count::try_from(0).is_err());
+    }
+
     #[test]
     fn test_is_set_1() {
-        assert_eq!(Triple(&C1, &C2, &C3).is_set(), true);
+        assert!(Triple(C1, C2, C3).is_set());
     }
 
     #[test]
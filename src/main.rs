@@ -1,157 +1,537 @@
-use std::borrow::{Borrow, BorrowMut};
-use std::collections::HashMap;
-use std::rc::Rc;
 // use core::slice::SlicePattern;
-use std::vec;
-use clap::Parser;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io, process, thread};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 
-use image::{Pixel, GenericImage};
-use image::{ImageBuffer, Rgb, Luma};
+#[cfg(feature = "display-window")]
+use image::{ImageBuffer, Luma, Pixel, Rgb, RgbImage};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand::SeedableRng;
-use imageproc::contours::Contour;
 use rand_chacha::ChaCha8Rng;
 
 use setvision::*;
 
-use crate::tree::{add_child, TreeNode};
-mod tree;
+mod play;
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+   #[command(subcommand)]
+   command: Option<Command>,
+
    /// Seed: random number to shuffle cards with
-   #[arg(short, long)]
+   #[arg(short, long, global = true)]
    seed: Option<u64>,
 
+   /// Write the intermediate images of the vision pipeline to this directory, as numbered PNGs
+   #[arg(long, global = true)]
+   debug_dir: Option<PathBuf>,
+
+   /// How to print the sets, statistics, counts or puzzle
+   #[arg(long, value_enum, global = true, default_value_t = Format::Text)]
+   format: Format,
+
+   /// What to look for on the table. Only `solve`, `solve-photo` and the dealt table take other rules than classic
+   #[arg(long, value_enum, global = true, default_value_t = RuleSet::Classic)]
+   rules: RuleSet,
+
    /// Image path: where to load an image from?
    img_path: Option<String>
 }
 
-fn to_rgb(image: &ImageBuffer<Luma<u8>, Vec<u8>>) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
-    ImageBuffer::from_fn(image.width(), image.height(),
-        |x, y| image.get_pixel(x, y).to_rgb())
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+   /// The table with every set highlighted
+   Text,
+   /// The table and every set as JSON
+   #[cfg(feature = "serde")]
+   Json,
 }
 
-// #[cfg(feature = "display-window")]
-fn main() {
-    use imageproc::window::display_multiple_images;
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RuleSet {
+   /// Three cards that are all the same or all different on every attribute
+   Classic,
+   /// Two pairs that are completed by the same card, also accepted as `ultra`
+   #[value(alias = "ultra")]
+   Superset,
+   /// A deck with 4 values for every attribute, where a set has 4 cards. Cards are written as digits, like `0123`
+   Four,
+}
+
+impl RuleSet {
+    /// The rules for a table of classic cards
+    fn rules(self) -> Result<Rules, Error> {
+        match self {
+            RuleSet::Classic => Ok(Rules::Classic),
+            RuleSet::Superset => Ok(Rules::SuperSet),
+            RuleSet::Four => Err(Error::InvalidVariant("the 4-value deck has no classic cards".to_string())),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+   /// Find all sets in a table of cards written like `2RSo` (count, color, shading, shape)
+   Solve {
+      /// The cards on the table, like `solve 1RFd 2GOo 3PSs`
+      cards: Vec<String>,
+
+      /// File to read the table from instead. Reads stdin when there are no cards and no file, or when it is `-`
+      #[arg(short, long, conflicts_with = "cards")]
+      file: Option<String>,
+   },
+   /// Recognise the cards in a photo of a table and find all sets
+   SolvePhoto {
+      /// Photo of the table
+      img_path: String,
 
-    let args = Args::parse();
+      /// Write the photo with the sets outlined to this file (PNG or JPEG)
+      #[arg(short, long)]
+      output: Option<String>,
+   },
+   /// Play a game in the terminal
+   Play,
+   /// Deal many tables or play many games, and show how often every number of sets occurs
+   Simulate {
+      /// Number of deals or games
+      #[arg(short, long, default_value_t = 10000)]
+      runs: u64,
 
-    let mut all_cards = generate_all_cards();
+      /// Deal a table of this many cards instead of playing games. Can be given more than once
+      #[arg(long, value_parser = clap::value_parser!(u8).range(1..=81))]
+      cards: Vec<u8>,
 
-    let selected_cards: Vec<&Card> = if let Some(seed) = args.seed {
-        let mut rng = ChaCha8Rng::seed_from_u64(seed);
-        // cards.choose_multiple(&mut rng, 12).collect()
-        all_cards.shuffle(&mut rng);
-        all_cards.iter().take(12).collect()
+      /// Which set to take when playing games
+      #[arg(long, value_enum, default_value_t = PolicyArg::First)]
+      policy: PolicyArg,
+
+      /// Number of threads, by default one for every core
+      #[arg(long)]
+      threads: Option<usize>,
+   },
+   /// Count exactly how many tables of some cards have every number of sets
+   Exact {
+      /// Number of cards on the table
+      #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u8).range(1..=81))]
+      cards: u8,
+
+      /// Only count tables with at most this many sets, which is much faster.
+      /// More than 12 cards can only be counted with 0
+      #[arg(long)]
+      max_sets: Option<usize>,
+
+      /// Number of threads, by default one for every core
+      #[arg(long)]
+      threads: Option<usize>,
+   },
+   /// Deal a table with exactly some number of sets to find, the same for the same seed
+   Puzzle {
+      /// Number of cards on the table
+      #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u8).range(3..=81))]
+      cards: u8,
+
+      /// Number of sets on the table
+      #[arg(long, default_value_t = 6)]
+      sets: usize,
+
+      /// Whether the sets may share cards
+      #[arg(long, value_enum, default_value_t = OverlapArg::Any)]
+      overlap: OverlapArg,
+
+      /// Also show the sets
+      #[arg(long)]
+      solution: bool,
+   },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PolicyArg {
+   /// The set that is found first
+   First,
+   /// Any of the sets, at random
+   Random,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OverlapArg {
+   /// Sets may share cards or not
+   Any,
+   /// No card is in two sets
+   Disjoint,
+   /// Every set shares a card with another set
+   Overlapping,
+}
+
+fn read_table(table_path: Option<String>) -> io::Result<String> {
+    match table_path.as_deref() {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
     }
-    else {
-        let mut rng = thread_rng();
-        // cards.choose_multiple(&mut rng, 12).collect()
-        all_cards.shuffle(&mut rng);
-        all_cards.iter().take(12).collect()
-    };
+}
 
-    println!("These are all the sets in this table:");
-    println!("-------------------------------------");
-    let sets = find_all_sets(selected_cards.to_vec());
-    let solved_table = Table {
-        cards: selected_cards,
-        triples: sets.into(),
-    };
-    println!("{}", solved_table);
-
-    if let Some(path) = args.img_path {
-        let img = image::open(path).expect("No image found at provided path").to_rgb8();
-        let grayscaled = image::imageops::grayscale(&img);
-        let canny = imageproc::edges::canny(&grayscaled, 30.0, 50.0);
-        // let opened = imageproc::morphology::close(&canny, Norm::LInf, 1);
-        let contours: Vec<Contour<i32>> = imageproc::contours::find_contours(&canny);
-        // TODO: sort these contrours. Each contour has a parent that contains it, 
-        //  so make some sort of graph and start at the top for drawing
-
-        println!("There are {} contours found", contours.len());
-        let mut contour_img = ImageBuffer::new(img.width(), img.height());
-        contour_img.copy_from(&img, 0, 0).unwrap();
-
-        let mut contour_mapping = HashMap::new(); //<usize, TreeNode<Contour<i32>>>
-
-        for (index, contour) in contours.iter().enumerate() {
-            let contour_treenode = Rc::new(TreeNode::new_childless(contour));
-            contour_mapping.insert(index, contour_treenode);
-        }
-
-        for child_ref in contour_mapping.values() {
-            let child = child_ref.to_owned();
-            match child.value.parent {
-                Some(parent_index) => {
-                    let parent = contour_mapping.get(&parent_index).unwrap().borrow();
-                    add_child(parent, &child);
-                }
-                None => ()
+/// The table and all its sets, as printed by `--format json`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct Solution<'a> {
+    #[serde(flatten)]
+    table: &'a Table,
+    sets: Vec<Triple>,
+}
+
+fn print_sets(table: &Table, format: Format) {
+    match format {
+        Format::Text => {
+            println!("These are all the sets in this table:");
+            println!("-------------------------------------");
+            if table.triples().is_empty() {
+                println!("There are no sets in this table");
+                let cards: CardSet = table.cards().iter().collect();
+                let others = CardSet::full().difference(&cards);
+                println!(
+                    "{} other cards would make a set, {} can be added without one",
+                    cards.count_set_makers(&others),
+                    cards.extensions().len()
+                );
             }
+            println!("{}", table);
+        }
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            let solution = Solution { table, sets: table.sets().collect() };
+            println!("{}", serde_json::to_string_pretty(&solution).expect("A table can always be serialized"));
         }
+    }
+}
 
-        let colors = vec![ 
-            Rgb([0u8, 0u8, 0u8]),
-            Rgb([0u8, 0u8, 255u8]),
-            Rgb([0u8, 255u8, 0u8]),
-            Rgb([0u8, 255u8, 255u8]),
-            Rgb([255u8, 0u8, 0u8]),
-            Rgb([255u8, 0u8, 255u8]),
-            Rgb([255u8, 255u8, 0u8]),
-            Rgb([255u8, 255u8, 255u8])];
-
-        let mut levels = vec![Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new()];
-
-        for contour_node in contour_mapping.values() {
-            let level = contour_node.level();
-            levels[level].push(contour_node);
-        }
-
-        for level in levels {
-            for contour_ref in level {
-                let node = contour_ref.to_owned();
-                let color = colors[node.level()];
-                let contour = node.value;
-                // println!("There are {} points in this contour", contour.points.len());
-                if contour.points.len() > 1 {
-                    let polygon = contour.points.as_slice();
-                    imageproc::drawing::draw_polygon_mut(&mut contour_img, polygon, color);
-                }
+/// Can be printed as JSON, when built with the `serde` feature
+#[cfg(feature = "serde")]
+trait Json: serde::Serialize {}
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> Json for T {}
+#[cfg(not(feature = "serde"))]
+trait Json {}
+#[cfg(not(feature = "serde"))]
+impl<T> Json for T {}
+
+/// The cards and everything found among them, as printed by `--format json`
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct FoundAmong<'a, C, F> {
+    cards: &'a [C],
+    found: Vec<F>,
+}
+
+/// The cards and everything the rules find among them, one per line
+fn print_found<C: Json + fmt::Display, F: Json + fmt::Display>(cards: &[C], found: Vec<F>, name: &str, format: Format) {
+    match format {
+        Format::Text => {
+            let cards: Vec<String> = cards.iter().map(|card| card.to_string()).collect();
+            println!("{}", cards.join(" "));
+            println!("These are all the {name} among these cards:");
+            println!("-------------------------------------");
+            if found.is_empty() {
+                println!("There are no {name} among these cards");
+            }
+            for found in found {
+                println!("{found}");
             }
         }
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            let found = FoundAmong { cards, found };
+            println!("{}", serde_json::to_string_pretty(&found).expect("Cards can always be serialized"));
+        }
+    }
+}
+
+/// The cards of a set of a variant, written as their values
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+struct VariantSet(Vec<VariantCard>);
+
+impl fmt::Display for VariantSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards: Vec<String> = self.0.iter().map(|card| card.to_string()).collect();
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
+fn print_variant_sets(variant: SetVariant, cards: &[VariantCard], format: Format) {
+    let sets = variant
+        .find_all_sets(cards)
+        .into_iter()
+        .map(|set| VariantSet(set.into_iter().map(|position| cards[position].clone()).collect()))
+        .collect();
+    print_found(cards, sets, "sets", format);
+}
+
+/// A classic card, written as its notation like `2RSo`, and in JSON the same as any other card
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
+struct Notated(Card);
+
+impl fmt::Display for Notated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.notation())
+    }
+}
+
+/// Print what the rules find among classic cards
+fn solve_cards(cards: Vec<Card>, rules: RuleSet, format: Format) -> Result<(), Error> {
+    match rules.rules()? {
+        Rules::Classic => print_sets(&Table::solve(cards), format),
+        rules @ Rules::SuperSet => {
+            let found = rules.find_all(&cards);
+            let cards: Vec<Notated> = cards.into_iter().map(Notated).collect();
+            print_found(&cards, found, "super sets", format);
+        }
+    }
+    Ok(())
+}
+
+/// Only `solve`, `solve-photo` and the dealt table look for anything but classic sets
+fn classic_only(rules: RuleSet, command: &str) -> Result<(), Error> {
+    if rules != RuleSet::Classic {
+        return Err(Error::InvalidVariant(format!("{command} only knows the classic rules")));
+    }
+    Ok(())
+}
 
-        for contour_node in contour_mapping.values() {
-            if contour_node.level() == 1 {
-                // These are likely already the card outlines, but better be sure:
-                // Does it have 1 to 3 children?
-                let child_count = contour_node.children.borrow().len();
-                if child_count >= 1 && child_count <= 3 {
-                    let epsilon = 30.0;
-                    let points = imageproc::geometry::approximate_polygon_dp(contour_node.value.points.as_slice(), epsilon, true);
-
-                    println!("Card candidate has approx. polygon of {} points (using eps. {})", points.len(), epsilon);
-                    if points.len() == 4 {
-                        imageproc::drawing::draw_polygon_mut(&mut contour_img, points.as_slice(), colors[0]);
-                    }
-                    // imageproc::drawing::draw_polygon_mut(&mut contour_img, contour_node.value.points.as_slice(), colors[0]);
-                }
+/// Progress messages go to stderr when stdout is meant for other programs
+fn status(format: Format, message: String) {
+    match format {
+        Format::Text => println!("{message}"),
+        #[cfg(feature = "serde")]
+        Format::Json => eprintln!("{message}"),
+    }
+}
+
+fn solve(cards: Vec<String>, table_path: Option<String>, rules: RuleSet, format: Format) -> Result<(), Error> {
+    let input = if cards.is_empty() { read_table(table_path)? } else { cards.join(" ") };
+    if rules == RuleSet::Four {
+        let variant = SetVariant::SET_OF_FOUR;
+        print_variant_sets(variant, &variant.parse_cards(&input)?, format);
+        return Ok(());
+    }
+    solve_cards(parse_table(&input)?, rules, format)
+}
+
+fn write_debug_images(debug: &vision::DebugImages, dir: &Path, format: Format) -> Result<(), Error> {
+    let written = debug.write_to(dir)?;
+    status(format, format!("Wrote {} debug images to {}", written.len(), dir.display()));
+    Ok(())
+}
+
+fn solve_photo(
+    img_path: String,
+    output: Option<String>,
+    debug_dir: Option<PathBuf>,
+    rules: RuleSet,
+    format: Format,
+) -> Result<(), Error> {
+    let found_rules = rules.rules()?;
+    let img = image::open(&img_path)?.to_rgb8();
+    if let Some(dir) = debug_dir {
+        write_debug_images(&vision::debug_images(&img), &dir, format)?;
+    }
+    let recognized = vision::recognize_cards(&img);
+    if recognized.is_empty() {
+        return Err(Error::Recognition(format!("no cards found in {img_path}")));
+    }
+    let cards: Vec<Card> = recognized.iter().map(|r| r.card).collect();
+    let notations: Vec<String> = cards.iter().map(|card| card.notation()).collect();
+    status(format, format!("Recognised {} cards: {}", cards.len(), notations.join(" ")));
+
+    // Outline the sets even when the cards do not fit in 3 rows, to see what was recognised
+    if let Some(output) = output {
+        let found = found_rules.find_all_positions(&cards);
+        vision::annotate_sets(&img, &recognized, &found)
+            .save(&output)
+            .map_err(|error| Error::ImageEncode { path: PathBuf::from(&output), error })?;
+        status(format, format!("Outlined {} sets in {output}", found.len()));
+    }
+
+    solve_cards(cards, rules, format)
+}
+
+fn thread_count(threads: Option<usize>) -> usize {
+    threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+}
+
+fn simulate(simulation: Simulation, runs: u64, seed: Option<u64>, threads: Option<usize>, format: Format) {
+    // Show the seed, so the numbers can be reproduced
+    let seed = seed.unwrap_or_else(rand::random);
+    let threads = thread_count(threads);
+    let what = match simulation {
+        Simulation::Deals(_) => "deals",
+        Simulation::Games(_) => "games",
+    };
+    let plural = if threads == 1 { "" } else { "s" };
+    status(format, format!("Simulating {runs} {what} with seed {seed} on {threads} thread{plural}"));
+    let statistics = simulation.simulate(runs, seed, threads);
+    match format {
+        Format::Text => print!("{statistics}"),
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&statistics).expect("Statistics can always be serialized"));
+        }
+    }
+}
+
+fn exact(cards: usize, max_sets: Option<usize>, threads: Option<usize>, format: Format) {
+    let threads = thread_count(threads);
+    let plural = if threads == 1 { "" } else { "s" };
+    status(format, format!("Counting tables of {cards} cards on {threads} thread{plural}"));
+    let counts = count_tables(cards, max_sets, threads);
+    match format {
+        Format::Text => print!("{counts}"),
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&counts).expect("Counts can always be serialized"));
+        }
+    }
+}
+
+fn puzzle(generator: PuzzleGenerator, solution: bool, rng: &mut ChaCha8Rng, format: Format) -> Result<(), Error> {
+    let puzzle = generator.generate(rng)?;
+    match format {
+        Format::Text => {
+            println!(
+                "Find {} sets among these cards, difficulty {:.2} of 4:",
+                puzzle.table.triples().len(),
+                puzzle.difficulty
+            );
+            let row_length = puzzle.table.cards().len().div_ceil(3);
+            for row in puzzle.table.cards().chunks(row_length) {
+                let cards: Vec<String> = row.iter().map(|card| card.to_string()).collect();
+                println!("{}", cards.concat());
+            }
+            if solution {
+                print_sets(&puzzle.table, format);
             }
         }
-        
-        display_multiple_images("", &vec![
-            &img,
-            &to_rgb(&grayscaled),
-            &to_rgb(&canny),
-            // &to_rgb(&opened)
-            &contour_img,
-            ], 500, 500);
+        #[cfg(feature = "serde")]
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&puzzle).expect("A puzzle can always be serialized"));
+        }
+    }
+    Ok(())
+}
+
+fn show_image(path: String, debug_dir: Option<PathBuf>) -> Result<(), Error> {
+    let img = image::open(path)?.to_rgb8();
+    let debug = vision::debug_images(&img);
+    println!("Detected {} cards", debug.crops.len());
+
+    if let Some(dir) = &debug_dir {
+        write_debug_images(&debug, dir, Format::Text)?;
+    }
+    #[cfg(feature = "display-window")]
+    display_debug_images(&img, &debug);
+    #[cfg(not(feature = "display-window"))]
+    if debug_dir.is_none() {
+        println!("Pass --debug-dir, or build with the display-window feature, to see the intermediate images");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "display-window")]
+fn to_rgb(image: &ImageBuffer<Luma<u8>, Vec<u8>>) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    ImageBuffer::from_fn(image.width(), image.height(),
+        |x, y| image.get_pixel(x, y).to_rgb())
+}
+
+#[cfg(feature = "display-window")]
+fn display_debug_images(img: &RgbImage, debug: &vision::DebugImages) {
+    use imageproc::window::display_multiple_images;
+
+    display_multiple_images("", &[
+        img,
+        &to_rgb(&debug.grayscale),
+        &to_rgb(&debug.canny),
+        &debug.contours,
+        ], 500, 500);
+    let crops: Vec<&RgbImage> = debug.crops.iter().collect();
+    if !crops.is_empty() {
+        display_multiple_images("Cards", &crops, vision::CARD_CROP_WIDTH, vision::CARD_CROP_HEIGHT);
+    }
+}
+
+fn main() {
+    if let Err(error) = run(Args::parse()) {
+        eprintln!("Error: {error}");
+        process::exit(1);
     }
-    
 }
 
+fn run(args: Args) -> Result<(), Error> {
+    let mut rng = match args.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    match args.command {
+        Some(Command::Solve { cards, file }) => return solve(cards, file, args.rules, args.format),
+        Some(Command::SolvePhoto { img_path, output }) => {
+            return solve_photo(img_path, output, args.debug_dir, args.rules, args.format)
+        }
+        Some(Command::Simulate { runs, cards, policy, threads }) => {
+            classic_only(args.rules, "simulate")?;
+            let simulation = if cards.is_empty() {
+                Simulation::Games(match policy {
+                    PolicyArg::First => Policy::First,
+                    PolicyArg::Random => Policy::Random,
+                })
+            } else {
+                Simulation::Deals(cards.into_iter().map(usize::from).collect())
+            };
+            simulate(simulation, runs, args.seed, threads, args.format);
+            return Ok(());
+        }
+        Some(Command::Exact { cards, max_sets, threads }) => {
+            classic_only(args.rules, "exact")?;
+            if usize::from(cards) > MAX_EXACT_CARDS && max_sets != Some(0) {
+                let message = format!(
+                    "counting the tables of more than {MAX_EXACT_CARDS} cards takes far too long, \
+                     pass --max-sets 0 to count only the tables without a set"
+                );
+                Args::command().error(clap::error::ErrorKind::ValueValidation, message).exit();
+            }
+            exact(cards.into(), max_sets, threads, args.format);
+            return Ok(());
+        }
+        Some(Command::Puzzle { cards, sets, overlap, solution }) => {
+            classic_only(args.rules, "puzzle")?;
+            let overlap = match overlap {
+                OverlapArg::Any => Overlap::Any,
+                OverlapArg::Disjoint => Overlap::Disjoint,
+                OverlapArg::Overlapping => Overlap::Overlapping,
+            };
+            return puzzle(PuzzleGenerator { cards: cards.into(), sets, overlap }, solution, &mut rng, args.format);
+        }
+        Some(Command::Play) => {
+            classic_only(args.rules, "play")?;
+            play::play(Game::without_auto_extra(Deck::shuffled(&mut rng)))?;
+            return Ok(());
+        }
+        None => (),
+    }
+
+    if args.rules == RuleSet::Four {
+        let variant = SetVariant::SET_OF_FOUR;
+        let mut deck = variant.deck();
+        deck.shuffle(&mut rng);
+        print_variant_sets(variant, &deck[..12], args.format);
+    } else {
+        solve_cards(Deck::shuffled(&mut rng).deal(12), args.rules, args.format)?;
+    }
+
+    match args.img_path {
+        Some(path) => show_image(path, args.debug_dir),
+        None => Ok(()),
+    }
+}
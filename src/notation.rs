@@ -0,0 +1,265 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Card, Color, Count, Shading, Shape};
+
+// A card is written as 4 characters: count, color, shading and shape.
+// E.g. `2RSo` is two red striped ovals and `1PFd` is one purple full (solid) diamond.
+// Parsing is case-insensitive, the position of a character determines its meaning.
+
+impl Count {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '1' => Some(Count::One),
+            '2' => Some(Count::Two),
+            '3' => Some(Count::Three),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Count::One => '1',
+            Count::Two => '2',
+            Count::Three => '3',
+        }
+    }
+}
+
+impl Color {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'R' => Some(Color::Red),
+            'G' => Some(Color::Green),
+            'P' => Some(Color::Purple),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Color::Red => 'R',
+            Color::Green => 'G',
+            Color::Purple => 'P',
+        }
+    }
+}
+
+impl Shading {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'O' => Some(Shading::Open),
+            'F' => Some(Shading::Solid),
+            'S' => Some(Shading::Striped),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Shading::Open => 'O',
+            Shading::Solid => 'F',
+            Shading::Striped => 'S',
+        }
+    }
+}
+
+impl Shape {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_lowercase() {
+            'd' => Some(Shape::Diamond),
+            'o' => Some(Shape::Oval),
+            's' => Some(Shape::Squiggle),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Shape::Diamond => 'd',
+            Shape::Oval => 'o',
+            Shape::Squiggle => 's',
+        }
+    }
+}
+
+impl Card {
+    /// The compact notation of this card, e.g. `2RSo`. Parse it back with `str::parse::<Card>`.
+    pub fn notation(&self) -> String {
+        [
+            self.count.to_char(),
+            self.color.to_char(),
+            self.shading.to_char(),
+            self.shape.to_char(),
+        ]
+        .iter()
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    WrongLength(usize),
+    InvalidCount(char),
+    InvalidColor(char),
+    InvalidShading(char),
+    InvalidShape(char),
+}
+
+impl ParseCardError {
+    /// Index of the offending character within the card notation
+    pub fn offset(&self) -> usize {
+        match self {
+            ParseCardError::WrongLength(_) => 0,
+            ParseCardError::InvalidCount(_) => 0,
+            ParseCardError::InvalidColor(_) => 1,
+            ParseCardError::InvalidShading(_) => 2,
+            ParseCardError::InvalidShape(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCardError::WrongLength(len) => {
+                write!(f, "a card is 4 characters (count, color, shading, shape), got {len}")
+            }
+            ParseCardError::InvalidCount(c) => write!(f, "invalid count '{c}', expected 1, 2 or 3"),
+            ParseCardError::InvalidColor(c) => write!(f, "invalid color '{c}', expected R, G or P"),
+            ParseCardError::InvalidShading(c) => {
+                write!(f, "invalid shading '{c}', expected O (open), S (striped) or F (full)")
+            }
+            ParseCardError::InvalidShape(c) => {
+                write!(f, "invalid shape '{c}', expected d (diamond), o (oval) or s (squiggle)")
+            }
+        }
+    }
+}
+
+impl Error for ParseCardError {}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != 4 {
+            return Err(ParseCardError::WrongLength(chars.len()));
+        }
+        Ok(Card {
+            count: Count::from_char(chars[0]).ok_or(ParseCardError::InvalidCount(chars[0]))?,
+            color: Color::from_char(chars[1]).ok_or(ParseCardError::InvalidColor(chars[1]))?,
+            shading: Shading::from_char(chars[2]).ok_or(ParseCardError::InvalidShading(chars[2]))?,
+            shape: Shape::from_char(chars[3]).ok_or(ParseCardError::InvalidShape(chars[3]))?,
+        })
+    }
+}
+
+/// A card that could not be parsed, with the 1-based line and column where it went wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTableError {
+    pub line: usize,
+    pub column: usize,
+    pub token: String,
+    pub cause: ParseCardError,
+}
+
+impl fmt::Display for ParseTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: '{}': {}", self.line, self.column, self.token, self.cause)
+    }
+}
+
+impl Error for ParseTableError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// Parse a table of cards, separated by whitespace.
+/// Everything after a `#` on a line is a comment. The layout of rows is not significant.
+pub fn parse_table(input: &str) -> Result<Vec<Card>, ParseTableError> {
+    let mut cards = vec![];
+    for (line_index, line) in input.lines().enumerate() {
+        let content = match line.find('#') {
+            Some(comment_start) => &line[..comment_start],
+            None => line,
+        };
+
+        for token in content.split_whitespace() {
+            match token.parse::<Card>() {
+                Ok(card) => cards.push(card),
+                Err(cause) => {
+                    // The token is a slice of the line, so its byte offset follows from the pointers
+                    let byte_offset = token.as_ptr() as usize - line.as_ptr() as usize;
+                    let column = line[..byte_offset].chars().count() + cause.offset() + 1;
+                    return Err(ParseTableError {
+                        line: line_index + 1,
+                        column,
+                        token: token.to_string(),
+                        cause,
+                    });
+                }
+            }
+        }
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_all_cards;
+
+    #[test]
+    fn test_parse_card() {
+        let card: Card = "2RSo".parse().unwrap();
+        assert_eq!(
+            card,
+            Card {
+                color: Color::Red,
+                count: Count::Two,
+                shading: Shading::Striped,
+                shape: Shape::Oval,
+            }
+        );
+        assert_eq!("2rso".parse::<Card>(), Ok(card));
+    }
+
+    #[test]
+    fn test_notation_roundtrip() {
+        for card in generate_all_cards() {
+            assert_eq!(card.notation().parse::<Card>(), Ok(card));
+        }
+    }
+
+    #[test]
+    fn test_parse_card_errors() {
+        assert_eq!("2RS".parse::<Card>(), Err(ParseCardError::WrongLength(3)));
+        assert_eq!("4RSo".parse::<Card>(), Err(ParseCardError::InvalidCount('4')));
+        assert_eq!("2BSo".parse::<Card>(), Err(ParseCardError::InvalidColor('B')));
+        assert_eq!("2RXo".parse::<Card>(), Err(ParseCardError::InvalidShading('X')));
+        assert_eq!("2RSx".parse::<Card>(), Err(ParseCardError::InvalidShape('x')));
+    }
+
+    #[test]
+    fn test_parse_table() {
+        let input = "# A small table\n1GFs 2GOo 3GSd\n\n  3GFd   1PFs # trailing comment\n";
+        let cards = parse_table(input).unwrap();
+        assert_eq!(cards.len(), 5);
+        assert_eq!(cards[0].notation(), "1GFs");
+        assert_eq!(cards[4].notation(), "1PFs");
+    }
+
+    #[test]
+    fn test_parse_table_error_position() {
+        let input = "1GFs 2GOo 3GSd\n3GFd  1PXs\n";
+        let error = parse_table(input).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 9);
+        assert_eq!(error.token, "1PXs");
+        assert_eq!(error.cause, ParseCardError::InvalidShading('X'));
+    }
+}
@@ -0,0 +1,269 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use setvision::*;
+
+const LABELS: &str = "abcdefghijklmnopqrstuvwxyz";
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+const HELP: &str = "Press the letters of three cards to take a set, e.g. `adk`.
+  ?    hint: show a card of a set, costs a point
+  !    there is no set here: deal 3 more cards
+  Esc  quit";
+
+/// A game played in the terminal, with a score and a clock
+pub struct Play {
+    game: Game,
+    score: i32,
+    started: Instant,
+    last_set: Instant,
+    /// Cards shown as a hint since the last set was taken
+    hints: Vec<Card>,
+    message: String,
+    /// Labels pressed for the next set
+    typed: String,
+}
+
+/// Positions on the table of three different labels
+fn parse_selection(input: &str, table_size: usize) -> Result<[usize; 3], String> {
+    let labels: Vec<char> = input.chars().filter(|c| !c.is_whitespace()).collect();
+    if labels.len() != 3 {
+        return Err(format!("Pick 3 cards, got {}", labels.len()));
+    }
+    let mut positions = [0; 3];
+    for (i, label) in labels.iter().enumerate() {
+        positions[i] = LABELS
+            .find(label.to_ascii_lowercase())
+            .filter(|&position| position < table_size)
+            .ok_or(format!("There is no card {label}"))?;
+        if positions[..i].contains(&positions[i]) {
+            return Err(format!("Card {label} is picked twice"));
+        }
+    }
+    Ok(positions)
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Play {
+    pub fn new(game: Game) -> Self {
+        let now = Instant::now();
+        Play {
+            game,
+            score: 0,
+            started: now,
+            last_set: now,
+            hints: vec![],
+            message: HELP.to_string(),
+            typed: String::new(),
+        }
+    }
+
+    /// Act on a key pressed by the player: labels are collected until three cards are picked.
+    /// Returns false when the player quits.
+    pub fn press(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc => return false,
+            // Raw mode turns Ctrl-C into a key press
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('?') => self.hint(),
+            KeyCode::Char('!') => self.claim_no_set(),
+            KeyCode::Char(label) if label.is_ascii_alphabetic() => {
+                self.typed.push(label);
+                if self.typed.len() == 3 {
+                    let typed = std::mem::take(&mut self.typed);
+                    match parse_selection(&typed, self.game.table().len()) {
+                        Ok(positions) => self.claim(positions),
+                        Err(error) => self.message = error,
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                self.typed.pop();
+            }
+            _ => (),
+        }
+        true
+    }
+
+    fn claim(&mut self, positions: [usize; 3]) {
+        let cards = positions.map(|position| self.game.table()[position]);
+        match self.game.claim(cards) {
+            Ok(()) => {
+                self.score += 1;
+                self.message = format!("Set! Found in {}", format_duration(self.last_set.elapsed()));
+                self.last_set = Instant::now();
+                self.hints.clear();
+            }
+            Err(ClaimError::NotASet) => {
+                self.score -= 1;
+                self.message = Triple::new(cards[0], cards[1], cards[2]).explain().to_string();
+            }
+            Err(error) => self.message = error.to_string(),
+        }
+    }
+
+    fn claim_no_set(&mut self) {
+        match self.game.claim_no_set() {
+            Ok(()) => {
+                self.score += 1;
+                self.message = "Right, there is no set. Dealt 3 more cards".to_string();
+            }
+            Err(ClaimError::GameOver) => {
+                self.score += 1;
+                self.message = "Right, there is no set, and no cards are left".to_string();
+            }
+            Err(error) => {
+                self.score -= 1;
+                self.message = format!("Wrong, {error}");
+            }
+        }
+    }
+
+    fn hint(&mut self) {
+        let next = self
+            .game
+            .find_set()
+            .ok()
+            .and_then(|set| set.cards().into_iter().find(|card| !self.hints.contains(card)));
+        match next {
+            Some(card) => {
+                self.score -= 1;
+                self.hints.push(card);
+                self.message = "Highlighted a card of a set".to_string();
+            }
+            None if self.hints.is_empty() => self.message = "There is no set here".to_string(),
+            None => self.message = "The whole set is highlighted already".to_string(),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game.is_over()
+    }
+
+    /// The table in 3 rows, every card with its label
+    pub fn render(&self) -> String {
+        let table = self.game.table();
+        let columns = table.len().div_ceil(3).max(1);
+        let mut screen = String::new();
+        for (row, cards) in table.chunks(columns).enumerate() {
+            for (column, card) in cards.iter().enumerate() {
+                let label = LABELS.as_bytes()[row * columns + column] as char;
+                if self.hints.contains(card) {
+                    screen += &format!("{label} {}  ", HighlightedCard::new(card));
+                } else {
+                    screen += &format!("{label} {card}  ");
+                }
+            }
+            screen += "\n";
+        }
+        screen += &format!(
+            "\nScore {}   Sets {}   Deck {}   Time {}\n",
+            self.score,
+            self.game.taken().len(),
+            self.game.deck().len(),
+            format_duration(self.started.elapsed())
+        );
+        screen
+    }
+}
+
+/// Keys arrive without Enter while this lives
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // Nothing to do about a terminal that cannot be restored
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Play until the player quits or no sets are left, redrawing the clock every second
+pub fn play(game: Game) -> io::Result<()> {
+    let mut play = Play::new(game);
+    let raw_mode = RawMode::enable()?;
+    let mut stdout = io::stdout();
+    loop {
+        let screen = format!("{CLEAR_SCREEN}{}\n{}\n> {}", play.render(), play.message, play.typed);
+        // A newline in raw mode does not go back to the start of the line
+        write!(stdout, "{}", screen.replace('\n', "\r\n"))?;
+        stdout.flush()?;
+        if play.is_over() {
+            drop(raw_mode);
+            println!("\nNo sets left. You took {} sets for {} points", play.game.taken().len(), play.score);
+            return Ok(());
+        }
+        let next_second = Duration::from_secs(1) - Duration::from_nanos(play.started.elapsed().subsec_nanos().into());
+        if !event::poll(next_second)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            // Some terminals also report releasing the key
+            if key.kind == KeyEventKind::Press && !play.press(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("adk", 12), Ok([0, 3, 10]));
+        assert_eq!(parse_selection("A d K", 12), Ok([0, 3, 10]));
+        assert!(parse_selection("ab", 12).is_err());
+        assert!(parse_selection("abn", 12).is_err());
+        assert!(parse_selection("aba", 12).is_err());
+    }
+
+    fn press_keys(play: &mut Play, keys: &str) -> bool {
+        keys.chars().all(|key| play.press(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE)))
+    }
+
+    #[test]
+    fn test_play() {
+        let mut play = Play::new(Game::without_auto_extra(Deck::new()));
+        let table = play.game.table().to_vec();
+        let set = play.game.find_set().unwrap().cards();
+        let labels: String = set
+            .iter()
+            .map(|card| LABELS.as_bytes()[table.iter().position(|c| c == card).unwrap()] as char)
+            .collect();
+
+        assert!(press_keys(&mut play, "?"));
+        assert_eq!(play.score, -1);
+        assert_eq!(play.hints.len(), 1);
+        assert!(press_keys(&mut play, "!"));
+        assert_eq!(play.score, -2);
+
+        // A card picked by mistake can be taken back
+        assert!(press_keys(&mut play, &labels[..2]));
+        assert!(play.press(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE)));
+        assert_eq!(play.typed, labels[..1]);
+        assert!(press_keys(&mut play, &labels[1..]));
+        assert_eq!(play.score, -1);
+        assert_eq!(play.game.taken(), [set]);
+        assert!(play.hints.is_empty());
+        assert!(play.typed.is_empty());
+
+        assert!(press_keys(&mut play, "abn"));
+        assert_eq!(play.message, "There is no card n");
+        assert!(!play.press(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert!(!play.press(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)));
+    }
+}
@@ -0,0 +1,208 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::{CardSet, Deck, Error, Table, Triple, Verdict};
+
+/// Number of cards swapped before giving up on a puzzle
+const MAX_SWAPS: usize = 100_000;
+
+/// How the sets of a puzzle may share cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overlap {
+    #[default]
+    Any,
+    /// No card is in two sets
+    Disjoint,
+    /// Every set shares a card with another set
+    Overlapping,
+}
+
+/// What kind of table to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleGenerator {
+    pub cards: usize,
+    /// Number of sets on the table, exactly
+    pub sets: usize,
+    pub overlap: Overlap,
+}
+
+impl Default for PuzzleGenerator {
+    /// The classic daily puzzle: 6 sets among 12 cards
+    fn default() -> Self {
+        PuzzleGenerator {
+            cards: 12,
+            sets: 6,
+            overlap: Overlap::Any,
+        }
+    }
+}
+
+/// A table with all its sets
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    pub table: Table,
+    /// See `difficulty`
+    pub difficulty: f64,
+}
+
+/// How hard the sets are to find: the mean number of attributes that are all different, from 1 to 4.
+/// Cards that look alike stand out together, so sets that differ in every attribute are the hardest.
+pub fn difficulty(sets: &[Triple]) -> f64 {
+    if sets.is_empty() {
+        return 0.0;
+    }
+    let different: usize = sets
+        .iter()
+        .map(|set| {
+            let explanation = set.explain();
+            explanation.attributes.iter().filter(|report| report.verdict == Verdict::AllDifferent).count()
+        })
+        .sum();
+    different as f64 / sets.len() as f64
+}
+
+impl PuzzleGenerator {
+    /// How far the table is from a puzzle, 0 for a puzzle
+    fn distance(&self, table: &CardSet) -> usize {
+        let sets: Vec<CardSet> = table
+            .set_indices()
+            .map(|set| {
+                let mut cards = CardSet::empty();
+                for index in set {
+                    cards.insert_index(index);
+                }
+                cards
+            })
+            .collect();
+        let overlap = match self.overlap {
+            Overlap::Any => 0,
+            // Cards that are in more than one set
+            Overlap::Disjoint => 3 * sets.len() - sets.iter().fold(CardSet::empty(), |all, set| all.union(set)).len(),
+            // Sets that share no card with another set
+            Overlap::Overlapping => (0..sets.len())
+                .filter(|&n| sets.iter().enumerate().all(|(m, other)| m == n || sets[n].intersection(other).is_empty()))
+                .count(),
+        };
+        sets.len().abs_diff(self.sets) + overlap
+    }
+
+    /// Deal a table and swap cards with the deck until it is a puzzle. The same random numbers give the same puzzle.
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> Result<Puzzle, Error> {
+        if !(3..=81).contains(&self.cards) || !self.cards.is_multiple_of(3) {
+            return Err(Error::MalformedTable(format!(
+                "a table should have a multiple of 3 cards, from 3 to 81, got {}",
+                self.cards
+            )));
+        }
+        if self.overlap == Overlap::Disjoint && 3 * self.sets > self.cards {
+            return Err(Error::Puzzle(format!("{} cards cannot hold {} disjoint sets", self.cards, self.sets)));
+        }
+        if self.overlap == Overlap::Overlapping && self.sets == 1 {
+            return Err(Error::Puzzle("a single set cannot overlap another set".to_string()));
+        }
+
+        let mut table: CardSet = Deck::shuffled(rng).deal(self.cards).iter().collect();
+        let mut distance = self.distance(&table);
+        for _ in 0..MAX_SWAPS {
+            if distance == 0 {
+                break;
+            }
+            let on_table: Vec<u8> = table.indices().collect();
+            let in_deck: Vec<u8> = CardSet::full().difference(&table).indices().collect();
+            // With all cards on the table, there is nothing to swap with
+            let (Some(&out), Some(&into)) = (on_table.choose(rng), in_deck.choose(rng)) else {
+                break;
+            };
+            let mut swapped = table;
+            swapped.remove_index(out);
+            swapped.insert_index(into);
+            // Also take swaps that get no closer, to get away from tables that cannot become a puzzle by one swap
+            let swapped_distance = self.distance(&swapped);
+            if swapped_distance <= distance {
+                table = swapped;
+                distance = swapped_distance;
+            }
+        }
+        if distance > 0 {
+            return Err(Error::Puzzle(format!(
+                "no table of {} cards with exactly {} sets found",
+                self.cards, self.sets
+            )));
+        }
+
+        let mut cards: Vec<_> = table.iter().collect();
+        cards.shuffle(rng);
+        let table = Table::solve(cards);
+        let difficulty = difficulty(&table.sets().collect::<Vec<_>>());
+        Ok(Puzzle { table, difficulty })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn card(notation: &str) -> crate::Card {
+        notation.parse().unwrap()
+    }
+
+    fn sets_share_cards(puzzle: &Puzzle) -> Vec<bool> {
+        let triples = puzzle.table.triples();
+        triples
+            .iter()
+            .enumerate()
+            .map(|(n, triple)| triples.iter().enumerate().any(|(m, other)| m != n && triple.iter().any(|p| other.contains(p))))
+            .collect()
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let easy = Triple::new(card("1RSd"), card("2RSd"), card("3RSd"));
+        let hard = Triple::new(card("1RSd"), card("2GFo"), card("3POs"));
+        assert_eq!(difficulty(&[easy]), 1.0);
+        assert_eq!(difficulty(&[hard]), 4.0);
+        assert_eq!(difficulty(&[easy, hard]), 2.5);
+        assert_eq!(difficulty(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        let puzzle = PuzzleGenerator::default().generate(&mut rng).unwrap();
+        assert_eq!(puzzle.table.cards().len(), 12);
+        assert_eq!(puzzle.table.triples().len(), 6);
+        assert!((1.0..=4.0).contains(&puzzle.difficulty));
+        assert_eq!(puzzle, PuzzleGenerator::default().generate(&mut ChaCha8Rng::seed_from_u64(6)).unwrap());
+
+        let disjoint = PuzzleGenerator { sets: 4, overlap: Overlap::Disjoint, ..Default::default() };
+        let puzzle = disjoint.generate(&mut rng).unwrap();
+        assert_eq!(puzzle.table.triples().len(), 4);
+        assert!(sets_share_cards(&puzzle).iter().all(|shares| !shares));
+
+        let overlapping = PuzzleGenerator { sets: 3, overlap: Overlap::Overlapping, ..Default::default() };
+        let puzzle = overlapping.generate(&mut rng).unwrap();
+        assert_eq!(puzzle.table.triples().len(), 3);
+        assert!(sets_share_cards(&puzzle).iter().all(|&shares| shares));
+
+        let none = PuzzleGenerator { sets: 0, ..Default::default() };
+        assert!(none.generate(&mut rng).unwrap().table.triples().is_empty());
+    }
+
+    #[test]
+    fn test_impossible_puzzles() {
+        let mut rng = ChaCha8Rng::seed_from_u64(6);
+        let too_many = PuzzleGenerator { sets: 5, overlap: Overlap::Disjoint, ..Default::default() };
+        assert!(matches!(too_many.generate(&mut rng), Err(Error::Puzzle(_))));
+        let alone = PuzzleGenerator { sets: 1, overlap: Overlap::Overlapping, ..Default::default() };
+        assert!(matches!(alone.generate(&mut rng), Err(Error::Puzzle(_))));
+        let no_cards = PuzzleGenerator { cards: 0, ..Default::default() };
+        assert!(matches!(no_cards.generate(&mut rng), Err(Error::MalformedTable(_))));
+        let whole_deck = PuzzleGenerator { cards: 81, sets: 6, ..Default::default() };
+        assert!(matches!(whole_deck.generate(&mut rng), Err(Error::Puzzle(_))));
+        let uneven = PuzzleGenerator { cards: 13, ..Default::default() };
+        assert!(matches!(uneven.generate(&mut rng), Err(Error::MalformedTable(_))));
+    }
+}
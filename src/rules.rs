@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{find_all_set_positions, find_all_sets, Card, Triple};
+
+/// Four cards that make two pairs, where both pairs are completed to a set by the same card.
+/// That card need not be on the table. The game Ultra SET calls this an ultra set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuperSet {
+    pub pairs: [[Card; 2]; 2],
+    /// The card that makes a set with either pair
+    pub completion: Card,
+}
+
+impl SuperSet {
+    /// The super set among four cards, if any. Four different cards can be paired up in at most one way.
+    pub fn among(cards: [Card; 4]) -> Option<SuperSet> {
+        let [a, b, c, d] = cards;
+        [[[a, b], [c, d]], [[a, c], [b, d]], [[a, d], [b, c]]]
+            .into_iter()
+            .find(|[first, second]| first[0].complete_set(&first[1]) == second[0].complete_set(&second[1]))
+            .map(|pairs| SuperSet {
+                pairs,
+                completion: pairs[0][0].complete_set(&pairs[0][1]),
+            })
+    }
+
+    pub fn cards(&self) -> [Card; 4] {
+        [self.pairs[0][0], self.pairs[0][1], self.pairs[1][0], self.pairs[1][1]]
+    }
+}
+
+impl fmt::Display for SuperSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [[a, b], [c, d]] = self.pairs.map(|pair| pair.map(|card| card.notation()));
+        write!(f, "{a} {b} + {c} {d} -> {}", self.completion.notation())
+    }
+}
+
+/// All super sets among the cards. A pair of the same card twice is never part of one.
+pub fn find_all_super_sets(cards: &[Card]) -> Vec<SuperSet> {
+    find_all_super_set_positions(cards)
+        .into_iter()
+        .map(|[a, b, c, d]| SuperSet {
+            pairs: [[cards[a], cards[b]], [cards[c], cards[d]]],
+            completion: cards[a].complete_set(&cards[b]),
+        })
+        .collect()
+}
+
+/// All super sets among the cards, as positions: the first pair and then the second pair
+pub fn find_all_super_set_positions(cards: &[Card]) -> Vec<[usize; 4]> {
+    // Pairs of positions, by the card that completes them
+    let mut pairs: HashMap<Card, Vec<[usize; 2]>> = HashMap::new();
+    for i in 0..cards.len() {
+        for j in i + 1..cards.len() {
+            if cards[i] != cards[j] {
+                pairs.entry(cards[i].complete_set(&cards[j])).or_default().push([i, j]);
+            }
+        }
+    }
+    let mut super_sets: Vec<[usize; 4]> = vec![];
+    for pairs in pairs.values() {
+        for (n, first) in pairs.iter().enumerate() {
+            for second in &pairs[n + 1..] {
+                // Pairs with the same completion share no card, unless a card is on the table twice
+                if !first.iter().any(|position| second.contains(position)) {
+                    super_sets.push([first[0], first[1], second[0], second[1]]);
+                }
+            }
+        }
+    }
+    // The same order for the same table
+    super_sets.sort_by_key(|positions| {
+        let mut sorted = *positions;
+        sorted.sort();
+        sorted
+    });
+    super_sets
+}
+
+/// Which cards to look for on a table of classic cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rules {
+    /// Three cards that are all the same or all different on every attribute
+    #[default]
+    Classic,
+    /// Two pairs that are completed by the same card, see `SuperSet`
+    SuperSet,
+}
+
+/// A structure that the rules look for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Found {
+    Set(Triple),
+    SuperSet(SuperSet),
+}
+
+impl Found {
+    pub fn cards(&self) -> Vec<Card> {
+        match self {
+            Found::Set(triple) => triple.cards().to_vec(),
+            Found::SuperSet(super_set) => super_set.cards().to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Found::Set(triple) => {
+                let notations = triple.cards().map(|card| card.notation());
+                write!(f, "{}", notations.join(" "))
+            }
+            Found::SuperSet(super_set) => write!(f, "{super_set}"),
+        }
+    }
+}
+
+impl Rules {
+    /// Number of cards to take from the table
+    pub fn size(&self) -> usize {
+        match self {
+            Rules::Classic => 3,
+            Rules::SuperSet => 4,
+        }
+    }
+
+    /// Positions of the cards of everything found, in the order of `find_all`
+    pub fn find_all_positions(&self, cards: &[Card]) -> Vec<Vec<usize>> {
+        match self {
+            Rules::Classic => find_all_set_positions(cards).into_iter().map(Vec::from).collect(),
+            Rules::SuperSet => find_all_super_set_positions(cards).into_iter().map(Vec::from).collect(),
+        }
+    }
+
+    pub fn find_all(&self, cards: &[Card]) -> Vec<Found> {
+        match self {
+            Rules::Classic => find_all_sets(cards).into_iter().map(Found::Set).collect(),
+            Rules::SuperSet => find_all_super_sets(cards).into_iter().map(Found::SuperSet).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_all_cards;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_super_set_among() {
+        // 1RFd 2RFd complete to 3RFd, and so do 3GOo 3PSs
+        let cards = ["1RFd", "3GOo", "2RFd", "3PSs"].map(card);
+        let super_set = SuperSet::among(cards).unwrap();
+        assert_eq!(super_set.completion, card("3RFd"));
+        assert_eq!(super_set.pairs, [["1RFd", "2RFd"].map(card), ["3GOo", "3PSs"].map(card)]);
+        assert_eq!(super_set.to_string(), "1RFd 2RFd + 3GOo 3PSs -> 3RFd");
+
+        assert_eq!(SuperSet::among(["1RFd", "2RFd", "3RFd", "1GFd"].map(card)), None);
+    }
+
+    #[test]
+    fn test_find_all_super_sets() {
+        let cards = ["1RFd", "3GOo", "2RFd", "3PSs", "2GSd"].map(card);
+        let super_sets = find_all_super_sets(&cards);
+        assert_eq!(super_sets.len(), 1);
+        assert_eq!(super_sets[0].completion, card("3RFd"));
+
+        let deck = generate_all_cards();
+        let super_sets = find_all_super_sets(&deck[..27]);
+        assert!(super_sets.iter().all(|super_set| SuperSet::among(super_set.cards()).is_some()));
+        let brute_force = (0..27)
+            .flat_map(|a| (a + 1..27).flat_map(move |b| (b + 1..27).flat_map(move |c| (c + 1..27).map(move |d| [a, b, c, d]))))
+            .filter(|positions| SuperSet::among(positions.map(|p| deck[p])).is_some())
+            .count();
+        assert_eq!(super_sets.len(), brute_force);
+    }
+
+    #[test]
+    fn test_rules() {
+        let cards = ["1RFd", "3GOo", "2RFd", "3PSs", "2GSd"].map(card);
+        assert_eq!(Rules::Classic.find_all(&cards), []);
+        let found = Rules::SuperSet.find_all(&cards);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].cards().len(), Rules::SuperSet.size());
+        assert_eq!(found[0].to_string(), "1RFd 2RFd + 3GOo 3PSs -> 3RFd");
+        assert_eq!(Rules::SuperSet.find_all_positions(&cards), [vec![0, 2, 1, 3]]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_found() {
+        let found = Rules::SuperSet.find_all(&["1RFd", "3GOo", "2RFd", "3PSs"].map(card));
+        let json = serde_json::to_string(&found[0]).unwrap();
+        assert!(json.starts_with(r#"{"superset":{"pairs":[[{"color":"red","#), "{json}");
+        assert_eq!(serde_json::from_str::<Found>(&json).unwrap(), found[0]);
+        let set = Found::Set(Triple::new(card("1RFd"), card("2RFd"), card("3RFd")));
+        assert!(serde_json::to_string(&set).unwrap().starts_with(r#"{"set":[{"#));
+    }
+}
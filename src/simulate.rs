@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::cardset::CARD_COUNT;
+use crate::{find_all_sets, CardSet, Deck, Game};
+
+/// How often every value occurred
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Histogram {
+    /// The number of times every value occurred, by value
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    pub fn add(&mut self, value: usize) {
+        if self.counts.len() <= value {
+            self.counts.resize(value + 1, 0);
+        }
+        self.counts[value] += 1;
+    }
+
+    pub fn merge(&mut self, other: &Histogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let sum: u64 = self.counts.iter().enumerate().map(|(value, count)| value as u64 * count).sum();
+        sum as f64 / self.total().max(1) as f64
+    }
+
+    /// The part of all occurrences that had this value
+    pub fn fraction(&self, value: usize) -> f64 {
+        self.counts.get(value).copied().unwrap_or(0) as f64 / self.total().max(1) as f64
+    }
+
+    /// The values that occurred, with how often
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.counts.iter().copied().enumerate().filter(|&(_, count)| count > 0)
+    }
+}
+
+/// Which set a player takes when there are several
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+    /// The set `Game::find_set` finds first
+    #[default]
+    First,
+    /// Any of the sets, at random
+    Random,
+}
+
+/// What to simulate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Simulation {
+    /// Deal tables of these sizes from a shuffled deck
+    Deals(Vec<usize>),
+    /// Play games until the deck is empty and no set is left
+    Games(Policy),
+}
+
+/// Counts over all runs of a simulation
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistics {
+    pub runs: u64,
+    /// The number of sets on every table that was seen, by the number of cards on the table
+    pub sets_per_table: BTreeMap<usize, Histogram>,
+    /// The number of cards left on the table at the end of a game
+    pub leftover: Histogram,
+}
+
+impl Statistics {
+    fn add_table(&mut self, table: &CardSet) {
+        self.sets_per_table.entry(table.len()).or_default().add(table.count_sets());
+    }
+
+    pub fn merge(&mut self, other: &Statistics) {
+        self.runs += other.runs;
+        for (size, histogram) in &other.sets_per_table {
+            self.sets_per_table.entry(*size).or_default().merge(histogram);
+        }
+        self.leftover.merge(&other.leftover);
+    }
+
+    /// The part of the tables of this size without a set
+    pub fn no_set_frequency(&self, size: usize) -> Option<f64> {
+        self.sets_per_table.get(&size).map(|histogram| histogram.fraction(0))
+    }
+}
+
+/// Every value with the percentage of occurrences
+fn percentages(histogram: &Histogram) -> String {
+    let total = histogram.total() as f64;
+    let parts: Vec<String> = histogram
+        .iter()
+        .map(|(value, count)| format!("{value}: {:.1}%", 100.0 * count as f64 / total))
+        .collect();
+    parts.join("  ")
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Cards  Tables  No set  Mean sets  Sets on the table")?;
+        for (size, histogram) in &self.sets_per_table {
+            writeln!(
+                f,
+                "{size:>5}  {:>6}  {:>5.2}%  {:>9.2}  {}",
+                histogram.total(),
+                100.0 * histogram.fraction(0),
+                histogram.mean(),
+                percentages(histogram)
+            )?;
+        }
+        if self.leftover.total() > 0 {
+            writeln!(
+                f,
+                "Cards left at the end of a game, {:.2} on average: {}",
+                self.leftover.mean(),
+                percentages(&self.leftover)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Every run has its own stream of random numbers, so the result does not depend on the threads
+fn run_rng(seed: u64, run: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(run);
+    rng
+}
+
+fn play_game(policy: Policy, rng: &mut ChaCha8Rng, statistics: &mut Statistics) {
+    let mut game = Game::from_deck(Deck::shuffled(rng));
+    while !game.is_over() {
+        statistics.add_table(&game.table().iter().collect());
+        let set = match policy {
+            Policy::First => game.find_set().ok(),
+            Policy::Random => find_all_sets(game.table()).choose(rng).copied(),
+        };
+        // A game without a set on the table is over, or more cards were dealt already
+        let Some(set) = set else {
+            break;
+        };
+        game.claim(set.cards()).expect("A found set can be taken");
+    }
+    statistics.leftover.add(game.table().len());
+}
+
+impl Simulation {
+    fn run(&self, seed: u64, run: u64, statistics: &mut Statistics) {
+        let mut rng = run_rng(seed, run);
+        match self {
+            Simulation::Deals(sizes) => {
+                // Only shuffle the cards that are dealt, and keep the table as bits, so a deal allocates nothing
+                let mut deck: [u8; CARD_COUNT as usize] = std::array::from_fn(|index| index as u8);
+                for &size in sizes {
+                    let (dealt, _) = deck.partial_shuffle(&mut rng, size);
+                    let mut table = CardSet::empty();
+                    for &index in dealt.iter() {
+                        table.insert_index(index);
+                    }
+                    statistics.add_table(&table);
+                }
+            }
+            Simulation::Games(policy) => play_game(*policy, &mut rng, statistics),
+        }
+        statistics.runs += 1;
+    }
+
+    /// Do `runs` deals or games on `threads` threads. The same seed gives the same statistics.
+    pub fn simulate(&self, runs: u64, seed: u64, threads: usize) -> Statistics {
+        let threads = threads.max(1) as u64;
+        let mut statistics = Statistics::default();
+        thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|first| {
+                    scope.spawn(move || {
+                        let mut statistics = Statistics::default();
+                        for run in (first..runs).step_by(threads as usize) {
+                            self.run(seed, run, &mut statistics);
+                        }
+                        statistics
+                    })
+                })
+                .collect();
+            for handle in handles {
+                statistics.merge(&handle.join().expect("A simulation thread panicked"));
+            }
+        });
+        statistics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        for value in [0, 2, 2, 4] {
+            histogram.add(value);
+        }
+        assert_eq!(histogram.total(), 4);
+        assert_eq!(histogram.mean(), 2.0);
+        assert_eq!(histogram.fraction(2), 0.5);
+        assert_eq!(histogram.fraction(7), 0.0);
+        assert_eq!(histogram.iter().collect::<Vec<_>>(), [(0, 1), (2, 2), (4, 1)]);
+
+        let mut other = Histogram::default();
+        other.add(6);
+        histogram.merge(&other);
+        assert_eq!(histogram.counts, [1, 0, 2, 0, 1, 0, 1]);
+        assert_eq!(percentages(&histogram), "0: 20.0%  2: 40.0%  4: 20.0%  6: 20.0%");
+    }
+
+    #[test]
+    fn test_deals_are_reproducible() {
+        let simulation = Simulation::Deals(vec![12, 15]);
+        let one_thread = simulation.simulate(200, 7, 1);
+        let four_threads = simulation.simulate(200, 7, 4);
+        assert_eq!(one_thread, four_threads);
+        assert_ne!(one_thread, simulation.simulate(200, 8, 4));
+
+        assert_eq!(one_thread.runs, 200);
+        assert_eq!(one_thread.sets_per_table[&12].total(), 200);
+        assert_eq!(one_thread.sets_per_table[&15].total(), 200);
+        // A 12 card table has 220 triples, of which about 1 in 79 is a set
+        let mean = one_thread.sets_per_table[&12].mean();
+        assert!((2.0..3.6).contains(&mean), "{mean}");
+        assert!(one_thread.no_set_frequency(12).unwrap() < 0.1);
+    }
+
+    #[test]
+    fn test_games() {
+        for policy in [Policy::First, Policy::Random] {
+            let statistics = Simulation::Games(policy).simulate(20, 3, 3);
+            assert_eq!(statistics.runs, 20);
+            assert_eq!(statistics.leftover.total(), 20);
+            // Every game starts with 12 cards or more, and ends with at most 20 cards without a set
+            assert!(statistics.sets_per_table.keys().all(|&size| size <= 21));
+            assert!(statistics.leftover.counts.len() <= 21);
+            assert_eq!(statistics, Simulation::Games(policy).simulate(20, 3, 1));
+        }
+    }
+}
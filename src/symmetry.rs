@@ -0,0 +1,192 @@
+use std::sync::OnceLock;
+
+use crate::cardset::{digits, from_digits, CARD_COUNT};
+use crate::{Card, CardSet};
+
+/// Swap the values of attributes and the attributes themselves. Sets stay sets, so tables that only
+/// differ by a relabeling play the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Relabeling {
+    /// Every attribute of a relabeled card takes the value of this attribute of the card, in the order of `Card::values`
+    attributes: [usize; 4],
+    /// The new value for every old value, by attribute of the relabeled card
+    values: [[u8; 3]; 4],
+}
+
+/// All orders of the numbers below N
+fn permutations<const N: usize>() -> Vec<[u8; N]> {
+    let mut permutations = vec![];
+    let mut permutation = [0; N];
+    fill_permutations(&mut permutation, 0, &mut permutations);
+    permutations
+}
+
+fn fill_permutations<const N: usize>(permutation: &mut [u8; N], filled: usize, permutations: &mut Vec<[u8; N]>) {
+    if filled == N {
+        permutations.push(*permutation);
+        return;
+    }
+    for value in 0..N as u8 {
+        if !permutation[..filled].contains(&value) {
+            permutation[filled] = value;
+            fill_permutations(permutation, filled + 1, permutations);
+        }
+    }
+}
+
+impl Relabeling {
+    pub const IDENTITY: Relabeling = Relabeling {
+        attributes: [0, 1, 2, 3],
+        values: [[0, 1, 2]; 4],
+    };
+
+    /// All 31104 relabelings: 24 orders of the attributes times 6 orders of the values of each
+    pub fn all() -> Vec<Relabeling> {
+        let value_orders = permutations::<3>();
+        let mut relabelings = vec![];
+        for attributes in permutations::<4>() {
+            for n in 0..value_orders.len().pow(4) {
+                let orders = [n / 216, n / 36 % 6, n / 6 % 6, n % 6];
+                relabelings.push(Relabeling {
+                    attributes: attributes.map(usize::from),
+                    values: orders.map(|order| value_orders[order]),
+                });
+            }
+        }
+        relabelings
+    }
+
+    fn apply_values(&self, values: &[u8; 4]) -> [u8; 4] {
+        [0, 1, 2, 3].map(|i| self.values[i][values[self.attributes[i]] as usize])
+    }
+
+    pub fn apply(&self, card: &Card) -> Card {
+        Card::from_values(&self.apply_values(&card.values())).expect("Relabeled values are valid")
+    }
+
+    /// The number of the relabeled card, by the number of the card
+    fn index_map(&self) -> [u8; CARD_COUNT as usize] {
+        let mut map = [0; CARD_COUNT as usize];
+        for index in 0..CARD_COUNT {
+            map[index as usize] = from_digits(self.apply_values(&digits(index)));
+        }
+        map
+    }
+}
+
+/// The same table for all tables that only differ by a `Relabeling` and the order of the cards:
+/// of all relabelings of the table, the one with the lowest card numbers
+pub fn canonical_table(cards: &[Card]) -> Vec<Card> {
+    static INDEX_MAPS: OnceLock<Vec<[u8; CARD_COUNT as usize]>> = OnceLock::new();
+    let maps = INDEX_MAPS.get_or_init(|| Relabeling::all().iter().map(Relabeling::index_map).collect());
+    let cards: Vec<usize> = cards.iter().map(|card| card.index().into()).collect();
+    let mut best: Option<Vec<u8>> = None;
+    let mut indices = Vec::with_capacity(cards.len());
+    for map in maps {
+        indices.clear();
+        indices.extend(cards.iter().map(|&card| map[card]));
+        indices.sort_unstable();
+        match &mut best {
+            Some(best) if indices >= *best => (),
+            Some(best) => best.clone_from(&indices),
+            None => best = Some(indices.clone()),
+        }
+    }
+    best.unwrap_or_default()
+        .into_iter()
+        .map(|index| Card::from_index(index).expect("Relabeled cards are in the deck"))
+        .collect()
+}
+
+/// Hash of the canonical table. It is the same on every platform and in every run, so it can be stored.
+pub fn canonical_hash(cards: &[Card]) -> u64 {
+    // 64 bit FNV-1a over the card numbers
+    canonical_table(cards).iter().fold(0xcbf2_9ce4_8422_2325, |hash, card| {
+        (hash ^ u64::from(card.index())).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Whether two tables only differ by a relabeling and the order of the cards
+pub fn are_equivalent(a: &[Card], b: &[Card]) -> bool {
+    a.len() == b.len() && canonical_table(a) == canonical_table(b)
+}
+
+impl CardSet {
+    /// The canonical table of these cards, see `canonical_table`
+    pub fn canonical(&self) -> CardSet {
+        canonical_table(&self.iter().collect::<Vec<_>>()).iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_all_sets, generate_all_cards, Deck};
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::HashSet;
+
+    fn card(notation: &str) -> Card {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn test_relabelings() {
+        let relabelings = Relabeling::all();
+        assert_eq!(relabelings.len(), 31104);
+        assert_eq!(relabelings.iter().collect::<HashSet<_>>().len(), 31104);
+        assert_eq!(relabelings[0], Relabeling::IDENTITY);
+
+        let deck = generate_all_cards();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        for relabeling in relabelings.choose_multiple(&mut rng, 50) {
+            let relabeled: HashSet<Card> = deck.iter().map(|card| relabeling.apply(card)).collect();
+            assert_eq!(relabeled.len(), 81);
+            let table: Vec<Card> = deck[..12].iter().map(|card| relabeling.apply(card)).collect();
+            assert_eq!(find_all_sets(&table).len(), find_all_sets(&deck[..12]).len());
+        }
+    }
+
+    #[test]
+    fn test_canonical_table() {
+        let mut rng = ChaCha8Rng::seed_from_u64(8);
+        let relabelings = Relabeling::all();
+        for _ in 0..5 {
+            let table = Deck::shuffled(&mut rng).deal(12);
+            let canonical = canonical_table(&table);
+            assert_eq!(canonical.len(), 12);
+            assert_eq!(canonical_table(&canonical), canonical);
+            let relabeling = relabelings.choose(&mut rng).unwrap();
+            let mut relabeled: Vec<Card> = table.iter().map(|card| relabeling.apply(card)).collect();
+            relabeled.shuffle(&mut rng);
+            assert_eq!(canonical_table(&relabeled), canonical);
+            assert_eq!(canonical_hash(&relabeled), canonical_hash(&table));
+            assert!(are_equivalent(&table, &relabeled));
+            let set: CardSet = table.iter().collect();
+            assert_eq!(set.canonical(), canonical.iter().collect());
+        }
+
+        // The first set in the deck is the smallest table of three cards that is a set
+        assert_eq!(canonical_table(&["3PFs", "3GFs", "3RFs"].map(card)), ["1ROd", "1ROo", "1ROs"].map(card));
+        assert!(!are_equivalent(&["1ROd", "1ROo", "1ROs"].map(card), &["1ROd", "1ROo", "1ROd"].map(card)));
+        assert_eq!(canonical_table(&[]), []);
+    }
+
+    #[test]
+    fn test_count_classes() {
+        // Two different cards differ in 1 to 4 attributes
+        let deck = generate_all_cards();
+        let pairs: HashSet<Vec<Card>> = deck.iter().map(|other| canonical_table(&[deck[0], *other])).collect();
+        assert_eq!(pairs.len(), 5);
+        let pairs: HashSet<Vec<Card>> = deck[1..].iter().map(|other| canonical_table(&[deck[0], *other])).collect();
+        assert_eq!(pairs.len(), 4);
+    }
+
+    #[test]
+    fn test_canonical_hash_is_stable() {
+        let table = ["1RSd", "2GFo", "3POs"].map(card);
+        assert_eq!(canonical_hash(&table), canonical_hash(&["3GSo", "1PFd", "2ROs"].map(card)));
+        assert_eq!(canonical_hash(&table), 15681201954889190687);
+    }
+}
@@ -1,73 +1,202 @@
-use std::rc::{Rc, Weak};
-use std::cell::RefCell;
-
-#[derive(Debug)]
-pub struct TreeNode<T> {
-    pub value: T,
-    pub children: RefCell<Vec<Rc<TreeNode<T>>>>,
-    pub parent: RefCell<Weak<TreeNode<T>>>
+use imageproc::contours::Contour;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    parent: Option<usize>,
+    children: Vec<usize>,
+    depth: usize,
 }
 
-impl<T> TreeNode<T> {
-    pub fn new(
-                value: T,
-                children: RefCell<Vec<Rc<TreeNode<T>>>>
-              ) -> Self {
-        TreeNode {
-            value,
-            children,
-            parent: RefCell::new(Weak::new()),
-        }
+/// How contours are nested in each other. A node has the same index as its contour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourTree {
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+}
+
+impl ContourTree {
+    pub fn new<T>(contours: &[Contour<T>]) -> Self {
+        let parents: Vec<Option<usize>> = contours.iter().map(|contour| contour.parent).collect();
+        Self::from_parents(&parents)
     }
 
-    pub fn new_childless(value: T) -> Self {
-        TreeNode {
-            value,
-            children: RefCell::new(vec![]),
-            parent: RefCell::new(Weak::new()),
+    /// Build the tree from the index of the parent of every node.
+    /// Panics when a parent does not exist or the parents form a cycle.
+    pub fn from_parents(parents: &[Option<usize>]) -> Self {
+        let mut nodes: Vec<Node> = parents
+            .iter()
+            .map(|&parent| Node {
+                parent,
+                children: vec![],
+                depth: 0,
+            })
+            .collect();
+        let mut roots = vec![];
+        for (index, &parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent) => {
+                    assert!(parent < nodes.len(), "Parent {} of {} does not exist", parent, index);
+                    nodes[parent].children.push(index);
+                }
+                None => roots.push(index),
+            }
+        }
+
+        // Set the depths from the roots down, so every node is visited once
+        let mut tree = ContourTree { nodes, roots };
+        let mut visited = 0;
+        let mut stack: Vec<(usize, usize)> = tree.roots.iter().map(|&root| (root, 0)).collect();
+        while let Some((index, depth)) = stack.pop() {
+            tree.nodes[index].depth = depth;
+            visited += 1;
+            stack.extend(tree.nodes[index].children.iter().map(|&child| (child, depth + 1)));
         }
+        assert_eq!(visited, tree.len(), "The parents form a cycle");
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The nodes without a parent
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
     }
 
-    pub fn level(&self) -> usize {
-        match self.parent.borrow().upgrade() {
-            Some(parent) => {
-                1 + parent.level()
-            },
-            None => 0
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.nodes[index].parent
+    }
+
+    pub fn children(&self, index: usize) -> &[usize] {
+        &self.nodes[index].children
+    }
+
+    /// Number of ancestors of a node, so 0 for a root
+    pub fn depth(&self, index: usize) -> usize {
+        self.nodes[index].depth
+    }
+
+    /// Depth of the deepest node, or 0 for an empty tree
+    pub fn max_depth(&self) -> usize {
+        self.nodes.iter().map(|node| node.depth).max().unwrap_or(0)
+    }
+
+    /// The parent of a node, its parent, and so on up to the root
+    pub fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.parent(index), move |&ancestor| self.parent(ancestor))
+    }
+
+    /// A node and everything nested in it, depth first with every node before its children
+    pub fn subtree(&self, index: usize) -> Subtree<'_> {
+        Subtree {
+            tree: self,
+            stack: vec![index],
         }
     }
+
+    /// Everything nested in a node, depth first, without the node itself
+    pub fn descendants(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.subtree(index).skip(1)
+    }
+
+    /// All nodes, every node before its children
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.roots.iter().flat_map(move |&root| self.subtree(root))
+    }
+
+    /// All indices, sorted from the outermost nodes to the most deeply nested ones
+    pub fn by_depth(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by_key(|&index| self.depth(index));
+        order
+    }
 }
 
-pub fn add_child<T>(parent: &Rc<TreeNode<T>>, child: &Rc<TreeNode<T>>) -> () {
-    parent.children.borrow_mut().push(Rc::clone(child));
-    *child.parent.borrow_mut() = Rc::downgrade(parent);
+/// Iterator over a node and its descendants, see `ContourTree::subtree`
+pub struct Subtree<'a> {
+    tree: &'a ContourTree,
+    stack: Vec<usize>,
 }
 
+impl Iterator for Subtree<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let index = self.stack.pop()?;
+        // Reversed, so the first child is visited first
+        self.stack.extend(self.tree.children(index).iter().rev());
+        Some(index)
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use imageproc::contours::BorderType;
+    use imageproc::point::Point;
+
+    //     0       5
+    //    / \      |
+    //   1   4     6
+    //  / \
+    // 2   3
+    const PARENTS: [Option<usize>; 7] = [None, Some(0), Some(1), Some(1), Some(0), None, Some(5)];
 
     #[test]
     fn test_create_tree() {
-        let mut a = Rc::new(TreeNode::new('a', RefCell::new(vec![])));
-        let mut b = Rc::new(TreeNode::new('b', RefCell::new(vec![])));
-        // let c = Rc::new(TreeNode::new('c', RefCell::new(vec![Rc::clone(&a), Rc::clone(&b)])));
-        let mut c = Rc::new(TreeNode::new('c', RefCell::new(vec![])));
-        add_child(&mut c, &mut a);
-        add_child(&mut c, &mut b);
-        let mut d = Rc::new(TreeNode::new('d', RefCell::new(vec![])));
-        // let e = Rc::new(TreeNode::new('e', RefCell::new(vec![Rc::clone(&c), Rc::clone(&d)])));
-        let mut e = Rc::new(TreeNode::new('e', RefCell::new(vec![])));
-        add_child(&mut e, &mut c);
-        add_child(&mut e, &mut d);
-        
-        println!("{:?}", e);
-
-        assert_eq!(e.level(), 0); // Top level
-        assert_eq!(d.level(), 1); // Child of e
-        assert_eq!(c.level(), 1); // Child of e
-        assert_eq!(b.level(), 2); // Child of c
-        assert_eq!(a.level(), 2); // Child of c
-    }
-}
\ No newline at end of file
+        let tree = ContourTree::from_parents(&PARENTS);
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.roots(), [0, 5]);
+        assert_eq!(tree.children(1), [2, 3]);
+        assert_eq!(tree.parent(3), Some(1));
+        assert_eq!(tree.parent(5), None);
+
+        assert_eq!(tree.depth(0), 0); // Top level
+        assert_eq!(tree.depth(4), 1); // Child of 0
+        assert_eq!(tree.depth(2), 2); // Child of 1
+        assert_eq!(tree.max_depth(), 2);
+    }
+
+    #[test]
+    fn test_iterate_tree() {
+        let tree = ContourTree::from_parents(&PARENTS);
+        assert_eq!(tree.subtree(1).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(tree.descendants(0).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(tree.descendants(6).count(), 0);
+        assert_eq!(tree.ancestors(3).collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(tree.by_depth(), [0, 5, 1, 4, 6, 2, 3]);
+    }
+
+    #[test]
+    fn test_deep_tree() {
+        // Deeper than any real photo, to make sure nothing recurses
+        let parents: Vec<Option<usize>> = (0..100_000).map(|index: usize| index.checked_sub(1)).collect();
+        let tree = ContourTree::from_parents(&parents);
+        assert_eq!(tree.max_depth(), 99_999);
+        assert_eq!(tree.subtree(0).count(), 100_000);
+        assert_eq!(tree.ancestors(99_999).count(), 99_999);
+    }
+
+    #[test]
+    fn test_tree_of_contours() {
+        let contour = |parent| Contour::new(vec![Point::new(0, 0), Point::new(1, 1)], BorderType::Outer, parent);
+        let tree = ContourTree::new(&[contour(None), contour(Some(0)), contour(Some(1))]);
+        assert_eq!(tree.depth(2), 2);
+
+        // The tree can be shared between threads
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&tree);
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle")]
+    fn test_cycle() {
+        ContourTree::from_parents(&[Some(1), Some(0)]);
+    }
+}
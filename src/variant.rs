@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Card, Color, Count, Error, Shading, Shape};
+
+/// The rules of a game of Set: how many attributes a card has, and how many values every
+/// attribute takes. A set has as many cards as there are values, and on every attribute
+/// those cards are all the same or all different.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetVariant {
+    attributes: usize,
+    values: u8,
+}
+
+/// A card of a variant: the value of every attribute, from 0 up to the number of values
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariantCard(Vec<u8>);
+
+impl VariantCard {
+    pub fn new(values: Vec<u8>) -> Self {
+        VariantCard(values)
+    }
+
+    pub fn values(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for VariantCard {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for VariantCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for value in &self.0 {
+            write!(f, "{value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the values are all the same or all different
+fn same_or_different(values: impl Iterator<Item = u8>, count: usize) -> bool {
+    let mut seen = vec![false; 256];
+    let mut distinct = 0;
+    for value in values {
+        if !seen[value as usize] {
+            seen[value as usize] = true;
+            distinct += 1;
+        }
+    }
+    distinct <= 1 || distinct == count
+}
+
+impl SetVariant {
+    /// The game as sold: count, color, shading and shape, with 3 values each
+    pub const CLASSIC: SetVariant = SetVariant { attributes: 4, values: 3 };
+
+    /// The beginner deck: only solid cards, so shading drops out
+    pub const BEGINNER: SetVariant = SetVariant { attributes: 3, values: 3 };
+
+    /// Four attributes with 4 values each, so a set has 4 cards
+    pub const SET_OF_FOUR: SetVariant = SetVariant { attributes: 4, values: 4 };
+
+    pub fn new(attributes: usize, values: u8) -> Result<Self, Error> {
+        if attributes == 0 {
+            return Err(Error::InvalidVariant("a card needs at least 1 attribute".to_string()));
+        }
+        if values < 3 {
+            return Err(Error::InvalidVariant(format!(
+                "an attribute needs at least 3 values, got {values}"
+            )));
+        }
+        if u32::try_from(attributes).ok().and_then(|n| (values as usize).checked_pow(n)).is_none() {
+            return Err(Error::InvalidVariant(format!(
+                "{values} values for {attributes} attributes are too many cards"
+            )));
+        }
+        Ok(SetVariant { attributes, values })
+    }
+
+    pub fn attributes(&self) -> usize {
+        self.attributes
+    }
+
+    pub fn values(&self) -> u8 {
+        self.values
+    }
+
+    /// Number of cards in a set, which is the number of values of an attribute
+    pub fn set_size(&self) -> usize {
+        self.values as usize
+    }
+
+    /// Number of different cards
+    pub fn card_count(&self) -> usize {
+        (self.values as usize).pow(self.attributes as u32)
+    }
+
+    fn is_card(&self, card: &[u8]) -> bool {
+        card.len() == self.attributes && card.iter().all(|&value| value < self.values)
+    }
+
+    /// Cards written as their values, like `0123`, separated by whitespace
+    pub fn parse_cards(&self, input: &str) -> Result<Vec<VariantCard>, Error> {
+        input
+            .split_whitespace()
+            .map(|word| {
+                let values: Option<Vec<u8>> = word.chars().map(|c| c.to_digit(10).map(|digit| digit as u8)).collect();
+                values.filter(|values| self.is_card(values)).map(VariantCard).ok_or_else(|| {
+                    Error::MalformedTable(format!(
+                        "'{word}' is not a card of {} digits from 0 to {}",
+                        self.attributes,
+                        self.values - 1
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Every card once, the first attribute changing slowest
+    pub fn deck(&self) -> Vec<VariantCard> {
+        let mut deck = vec![VariantCard(vec![])];
+        for _ in 0..self.attributes {
+            deck = deck
+                .into_iter()
+                .flat_map(|card| {
+                    (0..self.values).map(move |value| {
+                        let mut values = card.0.clone();
+                        values.push(value);
+                        VariantCard(values)
+                    })
+                })
+                .collect();
+        }
+        deck
+    }
+
+    /// Whether the cards are a set: the right number of cards, every attribute all the same or all different
+    pub fn is_set<C: AsRef<[u8]>>(&self, cards: &[C]) -> bool {
+        cards.len() == self.set_size()
+            && cards.iter().all(|card| self.is_card(card.as_ref()))
+            && (0..self.attributes)
+                .all(|attribute| same_or_different(cards.iter().map(|card| card.as_ref()[attribute]), cards.len()))
+    }
+
+    /// The only card that makes a set with these cards, one fewer than a set. There is none when
+    /// an attribute is neither all the same nor all different.
+    pub fn complete<C: AsRef<[u8]>>(&self, cards: &[C]) -> Option<VariantCard> {
+        if cards.len() + 1 != self.set_size() || !cards.iter().all(|card| self.is_card(card.as_ref())) {
+            return None;
+        }
+        let total: usize = (0..self.values as usize).sum();
+        (0..self.attributes)
+            .map(|attribute| {
+                let values = cards.iter().map(|card| card.as_ref()[attribute]);
+                let first = cards[0].as_ref()[attribute];
+                if values.clone().all(|value| value == first) {
+                    Some(first)
+                } else if same_or_different(values.clone(), cards.len()) {
+                    // All different: the value that is missing
+                    Some((total - values.map(usize::from).sum::<usize>()) as u8)
+                } else {
+                    None
+                }
+            })
+            .collect::<Option<Vec<u8>>>()
+            .map(VariantCard)
+    }
+
+    /// All sets among the cards, as positions in increasing order
+    pub fn find_all_sets<C: AsRef<[u8]>>(&self, cards: &[C]) -> Vec<Vec<usize>> {
+        let mut positions: HashMap<&[u8], Vec<usize>> = HashMap::new();
+        for (position, card) in cards.iter().enumerate() {
+            positions.entry(card.as_ref()).or_default().push(position);
+        }
+        let mut sets = vec![];
+        self.extend_sets(cards, &positions, &mut vec![], &mut sets);
+        sets
+    }
+
+    /// The first set among the cards, as positions in increasing order
+    pub fn find_set<C: AsRef<[u8]>>(&self, cards: &[C]) -> Result<Vec<usize>, Error> {
+        self.find_all_sets(cards).into_iter().next().ok_or(Error::NoSet)
+    }
+
+    /// Pick the cards of a set in increasing position, and look up the card that completes it
+    fn extend_sets<C: AsRef<[u8]>>(
+        &self,
+        cards: &[C],
+        positions: &HashMap<&[u8], Vec<usize>>,
+        chosen: &mut Vec<usize>,
+        sets: &mut Vec<Vec<usize>>,
+    ) {
+        if chosen.len() + 1 == self.set_size() {
+            let picked: Vec<&[u8]> = chosen.iter().map(|&position| cards[position].as_ref()).collect();
+            let Some(last) = self.complete(&picked) else {
+                return;
+            };
+            let after = *chosen.last().unwrap();
+            for &position in positions.get(last.values()).into_iter().flatten() {
+                if position > after {
+                    let mut set = chosen.clone();
+                    set.push(position);
+                    sets.push(set);
+                }
+            }
+            return;
+        }
+        let start = chosen.last().map_or(0, |last| last + 1);
+        for position in start..cards.len() {
+            chosen.push(position);
+            // Stop early when an attribute is already neither all the same nor all different
+            let consistent = (0..self.attributes).all(|attribute| {
+                same_or_different(chosen.iter().map(|&p| cards[p].as_ref()[attribute]), chosen.len())
+            });
+            if consistent {
+                self.extend_sets(cards, positions, chosen, sets);
+            }
+            chosen.pop();
+        }
+    }
+}
+
+impl Default for SetVariant {
+    fn default() -> Self {
+        SetVariant::CLASSIC
+    }
+}
+
+/// Position of a value among all values of its attribute
+fn digit<T: PartialEq + 'static>(mut values: std::slice::Iter<'static, T>, value: &T) -> u8 {
+    values.position(|v| v == value).unwrap() as u8
+}
+
+fn value<T: Copy + 'static>(mut values: std::slice::Iter<'static, T>, digit: u8) -> Option<T> {
+    values.nth(digit as usize).copied()
+}
+
+impl Card {
+    /// The card in the classic variant: color, count, shading and shape, each as the
+    /// position of the value in its `iterator()`
+    pub fn values(&self) -> [u8; 4] {
+        [
+            digit(Color::iterator(), &self.color),
+            digit(Count::iterator(), &self.count),
+            digit(Shading::iterator(), &self.shading),
+            digit(Shape::iterator(), &self.shape),
+        ]
+    }
+
+    /// The card with these values in the classic variant
+    pub fn from_values(values: &[u8]) -> Option<Card> {
+        match *values {
+            [color, count, shading, shape] => Some(Card {
+                color: value(Color::iterator(), color)?,
+                count: value(Count::iterator(), count)?,
+                shading: value(Shading::iterator(), shading)?,
+                shape: value(Shape::iterator(), shape)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_all_cards, Triple};
+
+    #[test]
+    fn test_new_variant() {
+        assert_eq!(SetVariant::new(4, 3).unwrap(), SetVariant::CLASSIC);
+        assert!(SetVariant::new(0, 3).is_err());
+        assert!(SetVariant::new(4, 2).is_err());
+        assert!(SetVariant::new(200, 3).is_err());
+        assert_eq!(SetVariant::new(5, 3).unwrap().card_count(), 243);
+    }
+
+    #[test]
+    fn test_classic_values() {
+        let deck = SetVariant::CLASSIC.deck();
+        assert_eq!(deck.len(), 81);
+        for (card, values) in generate_all_cards().iter().zip(&deck) {
+            assert_eq!(card.values(), values.values());
+            assert_eq!(Card::from_values(values.values()), Some(*card));
+        }
+        assert_eq!(Card::from_values(&[0, 0, 0, 3]), None);
+        assert_eq!(Card::from_values(&[0, 0, 0]), None);
+    }
+
+    #[test]
+    fn test_classic_sets() {
+        let cards = generate_all_cards();
+        let triple = Triple::new(cards[0], cards[40], cards[80]);
+        assert!(triple.is_set());
+        assert!(SetVariant::CLASSIC.is_set(&triple.cards().map(|card| card.values())));
+        assert_eq!(SetVariant::CLASSIC.find_all_sets(&SetVariant::CLASSIC.deck()).len(), 1080);
+    }
+
+    #[test]
+    fn test_classic_rules_agree() {
+        // `Triple` and `Card` have their own classic rules, which must be those of the variant
+        let cards = generate_all_cards();
+        let values: Vec<[u8; 4]> = cards.iter().map(Card::values).collect();
+        for (a, a_values) in cards.iter().zip(&values) {
+            for (b, b_values) in cards.iter().zip(&values) {
+                let completion = SetVariant::CLASSIC.complete(&[a_values, b_values]).unwrap();
+                assert_eq!(Card::from_values(completion.values()), Some(a.complete_set(b)));
+                for (c, c_values) in cards.iter().zip(&values) {
+                    let is_set = SetVariant::CLASSIC.is_set(&[a_values, b_values, c_values]);
+                    assert_eq!(Triple::new(*a, *b, *c).is_set(), is_set, "{a:?} {b:?} {c:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_beginner_and_extended() {
+        // Every pair of cards is in exactly one set
+        let beginner = SetVariant::BEGINNER.deck();
+        assert_eq!(beginner.len(), 27);
+        assert_eq!(SetVariant::BEGINNER.find_all_sets(&beginner).len(), 27 * 26 / 6);
+
+        let extended = SetVariant::new(5, 3).unwrap();
+        let a = VariantCard::new(vec![0, 1, 2, 0, 0]);
+        let b = VariantCard::new(vec![1, 1, 0, 0, 2]);
+        let c = extended.complete(&[&a, &b]).unwrap();
+        assert_eq!(c.values(), [2, 1, 1, 0, 1]);
+        assert!(extended.is_set(&[a, b, c]));
+    }
+
+    #[test]
+    fn test_four_values() {
+        let variant = SetVariant::new(2, 4).unwrap();
+        let cards: Vec<VariantCard> = [[0, 0], [1, 0], [2, 0]].map(|values| VariantCard::new(values.to_vec())).to_vec();
+        assert_eq!(variant.complete(&cards).unwrap().values(), [3, 0]);
+        // The second attribute is neither all the same nor all different
+        let mixed: Vec<VariantCard> = [[0, 0], [1, 0], [2, 1]].map(|values| VariantCard::new(values.to_vec())).to_vec();
+        assert_eq!(variant.complete(&mixed), None);
+
+        let deck = variant.deck();
+        let sets = variant.find_all_sets(&deck);
+        // 4 rows, 4 columns and 4! diagonals through the 4 by 4 grid
+        assert_eq!(sets.len(), 4 + 4 + 24);
+        assert!(sets.iter().all(|set| variant.is_set(&set.iter().map(|&p| &deck[p]).collect::<Vec<_>>())));
+        assert_eq!(variant.find_set(&deck[..3]).unwrap_err().to_string(), "there is no set among these cards");
+
+        let cards = SetVariant::SET_OF_FOUR.parse_cards("0123 1230\n2301 3012").unwrap();
+        assert!(SetVariant::SET_OF_FOUR.is_set(&cards));
+        assert_eq!(cards[1].to_string(), "1230");
+        assert!(SetVariant::SET_OF_FOUR.parse_cards("0124").is_err());
+        assert!(SetVariant::SET_OF_FOUR.parse_cards("012").is_err());
+    }
+}
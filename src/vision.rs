@@ -0,0 +1,148 @@
+use imageproc::contours::{BorderType, Contour};
+use imageproc::point::Point;
+
+use crate::tree::ContourTree;
+
+pub mod annotate;
+pub mod cards;
+pub mod color;
+pub mod debug;
+pub mod recognize;
+pub mod shading;
+pub mod shape;
+#[cfg(test)]
+mod synthetic;
+pub use annotate::annotate_sets;
+pub use cards::{detect_cards, detect_cards_in_contours, DetectedCard, CARD_CROP_HEIGHT, CARD_CROP_WIDTH};
+pub use color::{classify_table_colors, cluster_colors, symbol_color, Lab};
+pub use debug::{debug_images, DebugImages};
+pub use recognize::{recognize_cards, recognize_table, RecognizedCard};
+pub use shading::{classify_card_shadings, classify_shading, ShadingFeatures};
+pub use shape::{classify_card_shapes, classify_shape, ShapeFeatures};
+
+// Thresholds for the canny edge detection, as used in the original SetVision
+pub const CANNY_LOW: f32 = 30.0;
+pub const CANNY_HIGH: f32 = 50.0;
+
+// A symbol covers at least this fraction of the bounding box of its card
+const MIN_SYMBOL_AREA: f64 = 0.03;
+
+/// A recognised attribute of a card, with a confidence between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Classified<T> {
+    pub value: T,
+    pub confidence: f32,
+}
+
+impl<T: Copy> Classified<T> {
+    /// Pick the value with the highest score, confident when it stands out from the others
+    pub fn best_of(scores: &[(T, f64)]) -> Self {
+        let (value, best) = scores
+            .iter()
+            .copied()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("Nothing to choose from");
+        let total: f64 = scores.iter().map(|(_, score)| score).sum();
+        Classified {
+            value,
+            confidence: if total > 0.0 { (best / total) as f32 } else { 0.0 },
+        }
+    }
+}
+
+/// Linear ramp from 0 at `low` to 1 at `high`
+pub(crate) fn ramp(value: f64, low: f64, high: f64) -> f64 {
+    ((value - low) / (high - low)).clamp(0.0, 1.0)
+}
+
+/// Indices of the outlines of the symbols on a card, which are directly inside the card outline.
+/// Specks that are too small to be a symbol are left out, and so are edges within a symbol
+/// whose outline is not closed, as symbols do not overlap.
+pub fn symbol_contours(contours: &[Contour<i32>], tree: &ContourTree, card_index: usize) -> Vec<usize> {
+    let min_area = MIN_SYMBOL_AREA * bounding_box(&contours[card_index].points).area();
+    let candidates: Vec<(usize, BoundingBox)> = tree
+        .children(card_index)
+        .iter()
+        .filter(|&&index| contours[index].border_type == BorderType::Outer)
+        .map(|&index| (index, bounding_box(&contours[index].points)))
+        .filter(|(_, bounds)| bounds.area() >= min_area)
+        .collect();
+    candidates
+        .iter()
+        .filter(|(index, bounds)| {
+            !candidates
+                .iter()
+                .any(|(other, other_bounds)| other != index && other_bounds.contains(bounds))
+        })
+        .map(|(index, _)| *index)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BoundingBox {
+    min: Point<i32>,
+    max: Point<i32>,
+}
+
+impl BoundingBox {
+    fn area(&self) -> f64 {
+        (self.max.x - self.min.x) as f64 * (self.max.y - self.min.y) as f64
+    }
+
+    fn contains(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.min.x && self.min.y <= other.min.y && self.max.x >= other.max.x && self.max.y >= other.max.y
+    }
+}
+
+fn bounding_box(points: &[Point<i32>]) -> BoundingBox {
+    BoundingBox {
+        min: Point::new(
+            points.iter().map(|p| p.x).min().unwrap_or(0),
+            points.iter().map(|p| p.y).min().unwrap_or(0),
+        ),
+        max: Point::new(
+            points.iter().map(|p| p.x).max().unwrap_or(0),
+            points.iter().map(|p| p.y).max().unwrap_or(0),
+        ),
+    }
+}
+
+/// Area of a simple polygon using the shoelace formula
+pub fn polygon_area<T: Into<f64> + Copy>(points: &[Point<T>]) -> f64 {
+    let mut twice_area = 0.0;
+    for (index, point) in points.iter().enumerate() {
+        let next = points[(index + 1) % points.len()];
+        twice_area += point.x.into() * next.y.into() - next.x.into() * point.y.into();
+    }
+    twice_area.abs() / 2.0
+}
+
+/// The contours of an image and the cards found in them, the way `detect_cards` finds them
+#[cfg(test)]
+pub(crate) fn contours_and_cards(image: &image::RgbImage) -> (Vec<Contour<i32>>, Vec<DetectedCard>) {
+    let grayscaled = image::imageops::grayscale(image);
+    let canny = imageproc::edges::canny(&grayscaled, CANNY_LOW, CANNY_HIGH);
+    let contours = imageproc::contours::find_contours(&canny);
+    let detected = detect_cards_in_contours(image, &contours);
+    (contours, detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygon_area() {
+        let square = [Point::new(0, 0), Point::new(10, 0), Point::new(10, 10), Point::new(0, 10)];
+        assert_eq!(polygon_area(&square), 100.0);
+        let triangle = [Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(0.0, 3.0)];
+        assert_eq!(polygon_area(&triangle), 6.0);
+    }
+
+    #[test]
+    fn test_best_of() {
+        let classified = Classified::best_of(&[('a', 1.0), ('b', 3.0)]);
+        assert_eq!(classified.value, 'b');
+        assert_eq!(classified.confidence, 0.75);
+    }
+}
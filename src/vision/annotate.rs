@@ -0,0 +1,141 @@
+use image::imageops::{self, FilterType};
+use image::{Rgb, RgbImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_line_segment_mut};
+use imageproc::point::Point;
+use imageproc::rect::Rect;
+
+use super::RecognizedCard;
+
+// Colors to tell the sets apart, repeated when there are more sets
+const SET_COLORS: [Rgb<u8>; 8] = [
+    Rgb([255, 0, 0]),
+    Rgb([0, 200, 255]),
+    Rgb([255, 200, 0]),
+    Rgb([0, 220, 0]),
+    Rgb([255, 0, 255]),
+    Rgb([255, 120, 0]),
+    Rgb([0, 0, 255]),
+    Rgb([255, 255, 255]),
+];
+const CARD_OUTLINE_COLOR: Rgb<u8> = Rgb([128, 128, 128]);
+const LEGEND_BACKGROUND: Rgb<u8> = Rgb([40, 40, 40]);
+
+const OUTLINE_THICKNESS: u32 = 3;
+// Room between the outlines of different sets around the same card
+const OUTLINE_SPACING: u32 = 2;
+const THUMBNAIL_WIDTH: u32 = 36;
+const THUMBNAIL_HEIGHT: u32 = 56;
+const SWATCH_SIZE: u32 = 20;
+const PADDING: u32 = 6;
+
+pub fn set_color(index: usize) -> Rgb<u8> {
+    SET_COLORS[index % SET_COLORS.len()]
+}
+
+/// Draw the outline of a quadrilateral, `offset` pixels outside of it
+fn draw_outline(image: &mut RgbImage, corners: &[Point<f32>; 4], offset: u32, thickness: u32, color: Rgb<u8>) {
+    let center_x = corners.iter().map(|p| p.x).sum::<f32>() / 4.0;
+    let center_y = corners.iter().map(|p| p.y).sum::<f32>() / 4.0;
+    for width in 0..thickness {
+        let distance = (offset + width) as f32;
+        let expanded = corners.map(|p| {
+            let (dx, dy) = (p.x - center_x, p.y - center_y);
+            let length = dx.hypot(dy).max(1.0);
+            (p.x + dx / length * distance, p.y + dy / length * distance)
+        });
+        for i in 0..4 {
+            draw_line_segment_mut(image, expanded[i], expanded[(i + 1) % 4], color);
+        }
+    }
+}
+
+/// The photo with a colored outline around the cards of each set,
+/// and next to it a legend showing the cards of every set in its color.
+/// The sets are positions in `recognized`, as in `Table::triples`.
+pub fn annotate_sets<S: AsRef<[usize]>>(image: &RgbImage, recognized: &[RecognizedCard], sets: &[S]) -> RgbImage {
+    let row_height = THUMBNAIL_HEIGHT + PADDING;
+    let legend_width = match sets.iter().map(|set| set.as_ref().len()).max() {
+        None => 0,
+        Some(size) => PADDING + SWATCH_SIZE + PADDING + size as u32 * (THUMBNAIL_WIDTH + PADDING),
+    };
+    let legend_height = PADDING + sets.len() as u32 * row_height;
+    let mut annotated = RgbImage::from_pixel(
+        image.width() + legend_width,
+        image.height().max(legend_height),
+        LEGEND_BACKGROUND,
+    );
+    imageops::replace(&mut annotated, image, 0, 0);
+
+    for card in recognized {
+        draw_outline(&mut annotated, &card.detected.corners, 0, 1, CARD_OUTLINE_COLOR);
+    }
+
+    // A card can be part of several sets, so every set gets its own distance from the cards
+    let mut outlines_per_card = vec![0; recognized.len()];
+    for (set_index, set) in sets.iter().enumerate() {
+        let color = set_color(set_index);
+        let top = PADDING + set_index as u32 * row_height;
+        let swatch = Rect::at((image.width() + PADDING) as i32, (top + (THUMBNAIL_HEIGHT - SWATCH_SIZE) / 2) as i32)
+            .of_size(SWATCH_SIZE, SWATCH_SIZE);
+        draw_filled_rect_mut(&mut annotated, swatch, color);
+
+        for (position, &index) in set.as_ref().iter().enumerate() {
+            let offset = 2 + outlines_per_card[index] * (OUTLINE_THICKNESS + OUTLINE_SPACING);
+            outlines_per_card[index] += 1;
+            draw_outline(&mut annotated, &recognized[index].detected.corners, offset, OUTLINE_THICKNESS, color);
+
+            let thumbnail = imageops::resize(
+                &recognized[index].detected.crop,
+                THUMBNAIL_WIDTH,
+                THUMBNAIL_HEIGHT,
+                FilterType::Triangle,
+            );
+            let left = image.width() + 2 * PADDING + SWATCH_SIZE + position as u32 * (THUMBNAIL_WIDTH + PADDING);
+            imageops::replace(&mut annotated, &thumbnail, left as i64, top as i64);
+        }
+    }
+    annotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_all_set_positions;
+    use crate::vision::recognize_cards;
+    use crate::vision::synthetic::{card, synthetic_table};
+
+    #[test]
+    fn test_annotate_sets() {
+        // The first three cards form a set, the fourth is not part of any
+        let (image, _) = synthetic_table(&["1GFs", "2GOo", "3GSd", "2RSo"].map(card));
+        let recognized = recognize_cards(&image);
+        let cards: Vec<_> = recognized.iter().map(|r| r.card).collect();
+        let sets = find_all_set_positions(&cards);
+        assert_eq!(sets.len(), 1);
+
+        let annotated = annotate_sets(&image, &recognized, &sets);
+        assert!(annotated.width() > image.width());
+
+        // The swatch in the legend
+        let swatch_center = (
+            image.width() + PADDING + SWATCH_SIZE / 2,
+            PADDING + THUMBNAIL_HEIGHT / 2,
+        );
+        assert_eq!(*annotated.get_pixel(swatch_center.0, swatch_center.1), set_color(0));
+
+        // The outline, just above the middle of the top of a card in the set
+        let corners = recognized[sets[0][0]].detected.corners;
+        let top_x = (corners[0].x + corners[1].x) / 2.0;
+        let top_y = (corners[0].y + corners[1].y) / 2.0;
+        let outlined = (2..8).any(|dy| *annotated.get_pixel(top_x as u32, (top_y as u32).saturating_sub(dy)) == set_color(0));
+        assert!(outlined);
+    }
+
+    #[test]
+    fn test_annotate_without_sets() {
+        let (image, _) = synthetic_table(&["1GFs", "2RSo"].map(card));
+        let recognized = recognize_cards(&image);
+        let annotated = annotate_sets::<[usize; 3]>(&image, &recognized, &[]);
+        assert_eq!(annotated.dimensions(), image.dimensions());
+    }
+}
@@ -0,0 +1,220 @@
+use image::{Rgb, RgbImage};
+use imageproc::contours::{BorderType, Contour};
+use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+use imageproc::geometry::convex_hull;
+use imageproc::point::Point;
+
+use super::{polygon_area, symbol_contours, CANNY_HIGH, CANNY_LOW};
+use crate::tree::ContourTree;
+
+// Size of the upright card crops, a Set card is 57 x 89 mm
+pub const CARD_CROP_WIDTH: u32 = 180;
+pub const CARD_CROP_HEIGHT: u32 = 280;
+
+// Part of the convex hull of a card outline that the fitted quadrilateral must cover
+const MIN_HULL_COVERAGE: f64 = 0.9;
+// Cards smaller than this fraction of the image are considered noise
+const MIN_CARD_AREA: f64 = 0.002;
+
+#[derive(Debug, Clone)]
+pub struct DetectedCard {
+    /// Index of the card outline in the contours it was detected in
+    pub contour_index: usize,
+    /// Top-left, top-right, bottom-right and bottom-left corner of the upright card, in the photo
+    pub corners: [Point<f32>; 4],
+    /// The card warped to an upright image of `CARD_CROP_WIDTH` x `CARD_CROP_HEIGHT`
+    pub crop: RgbImage,
+    /// Indices of the outlines of the symbols on the card, see `symbol_contours`
+    pub symbols: Vec<usize>,
+}
+
+pub fn detect_cards(image: &RgbImage) -> Vec<DetectedCard> {
+    let grayscaled = image::imageops::grayscale(image);
+    let canny = imageproc::edges::canny(&grayscaled, CANNY_LOW, CANNY_HIGH);
+    let contours = imageproc::contours::find_contours(&canny);
+    detect_cards_in_contours(image, &contours)
+}
+
+/// Find cards among the contours of the edges in `image`.
+/// A card is the inside of a quadrilateral outline holding 1 to 3 symbols.
+pub fn detect_cards_in_contours(image: &RgbImage, contours: &[Contour<i32>]) -> Vec<DetectedCard> {
+    let tree = ContourTree::new(contours);
+    let min_area = MIN_CARD_AREA * image.width() as f64 * image.height() as f64;
+
+    // Visit the outermost contours first, so a diamond on a card is not mistaken for a card itself
+    let order = tree.by_depth();
+
+    let mut is_card = vec![false; contours.len()];
+    let mut cards = vec![];
+    for index in order {
+        let contour = &contours[index];
+        if contour.border_type != BorderType::Hole || tree.ancestors(index).any(|ancestor| is_card[ancestor]) {
+            continue;
+        }
+        let symbols = symbol_contours(contours, &tree, index);
+        if !(1..=3).contains(&symbols.len()) {
+            continue;
+        }
+
+        let corners = match card_corners(contour) {
+            Some(corners) if polygon_area(&corners) >= min_area => corners,
+            _ => continue,
+        };
+        if let Some(crop) = warp_card(image, &corners) {
+            is_card[index] = true;
+            cards.push(DetectedCard {
+                contour_index: index,
+                corners,
+                crop,
+                symbols,
+            });
+        }
+    }
+    // Report cards in reading order, regardless of their depth in the contour tree
+    cards.sort_by_key(|card| card.contour_index);
+    cards
+}
+
+/// Fit a quadrilateral to the convex hull of the contour: the longest diagonal
+/// and the points farthest away on either side of it.
+/// The fit must cover most of the hull, which rules out ovals and other round shapes.
+fn card_corners(contour: &Contour<i32>) -> Option<[Point<f32>; 4]> {
+    let hull = convex_hull(&contour.points);
+    if hull.len() < 4 {
+        return None;
+    }
+
+    let mut diagonal = (0, 0);
+    let mut longest = 0;
+    for (i, a) in hull.iter().enumerate() {
+        for (j, b) in hull.iter().enumerate().skip(i + 1) {
+            let length = (a.x - b.x).pow(2) + (a.y - b.y).pow(2);
+            if length > longest {
+                longest = length;
+                diagonal = (i, j);
+            }
+        }
+    }
+    let (a, c) = (hull[diagonal.0], hull[diagonal.1]);
+    let side = |p: &&Point<i32>| signed_distance_to_line(**p, a, c);
+    let b = *hull.iter().max_by(|p, q| side(p).total_cmp(&side(q)))?;
+    let d = *hull.iter().min_by(|p, q| side(p).total_cmp(&side(q)))?;
+
+    let quadrilateral = [a, b, c, d];
+    if polygon_area(&quadrilateral) < MIN_HULL_COVERAGE * polygon_area(&hull) {
+        return None;
+    }
+    Some(order_corners(quadrilateral.map(|p| Point::new(p.x as f32, p.y as f32))))
+}
+
+fn signed_distance_to_line(point: Point<i32>, start: Point<i32>, end: Point<i32>) -> f64 {
+    let (dx, dy) = ((end.x - start.x) as f64, (end.y - start.y) as f64);
+    let length = (dx * dx + dy * dy).sqrt();
+    (dy * (point.x - start.x) as f64 - dx * (point.y - start.y) as f64) / length
+}
+
+fn distance(a: Point<f32>, b: Point<f32>) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Order the corners clockwise, starting at the top-left corner of the card when held upright.
+/// Which of the two short sides becomes the top is arbitrary, as Set cards look the same upside down,
+/// so take the one that is highest in the image.
+pub fn order_corners(corners: [Point<f32>; 4]) -> [Point<f32>; 4] {
+    let center_x = corners.iter().map(|p| p.x).sum::<f32>() / 4.0;
+    let center_y = corners.iter().map(|p| p.y).sum::<f32>() / 4.0;
+    let mut clockwise = corners;
+    // With the y-axis pointing down, increasing angles go clockwise
+    clockwise.sort_by(|a, b| {
+        let angle_a = (a.y - center_y).atan2(a.x - center_x);
+        let angle_b = (b.y - center_y).atan2(b.x - center_x);
+        angle_a.total_cmp(&angle_b)
+    });
+
+    let short_side_start = if distance(clockwise[0], clockwise[1]) <= distance(clockwise[1], clockwise[2]) {
+        0
+    } else {
+        1
+    };
+    let side_height = |start: usize| clockwise[start].y + clockwise[(start + 1) % 4].y;
+    let top_start = if side_height(short_side_start) <= side_height(short_side_start + 2) {
+        short_side_start
+    } else {
+        short_side_start + 2
+    };
+    clockwise.rotate_left(top_start);
+    clockwise
+}
+
+fn warp_card(image: &RgbImage, corners: &[Point<f32>; 4]) -> Option<RgbImage> {
+    let (width, height) = (CARD_CROP_WIDTH as f32, CARD_CROP_HEIGHT as f32);
+    let from = corners.map(|p| (p.x, p.y));
+    let to = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    let projection = Projection::from_control_points(from, to)?;
+    let mut crop = RgbImage::new(CARD_CROP_WIDTH, CARD_CROP_HEIGHT);
+    warp_into(image, &projection, Interpolation::Bilinear, Rgb([0, 0, 0]), &mut crop);
+    Some(crop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::synthetic::{card, synthetic_table};
+
+    #[test]
+    fn test_order_corners() {
+        let expected = [
+            Point::new(10.0, 10.0),
+            Point::new(70.0, 10.0),
+            Point::new(70.0, 100.0),
+            Point::new(10.0, 100.0),
+        ];
+        let shuffled = [expected[2], expected[0], expected[3], expected[1]];
+        assert_eq!(order_corners(shuffled), expected);
+
+        // A card lying on its side still starts at a corner of a short side
+        let sideways = [
+            Point::new(10.0, 10.0),
+            Point::new(100.0, 10.0),
+            Point::new(100.0, 70.0),
+            Point::new(10.0, 70.0),
+        ];
+        let ordered = order_corners(sideways);
+        assert!(distance(ordered[0], ordered[1]) < distance(ordered[1], ordered[2]));
+    }
+
+    #[test]
+    fn test_detect_cards() {
+        let cards = [card("1RFd"), card("2GOo"), card("3PSs")];
+        let (image, expected_corners) = synthetic_table(&cards);
+
+        let detected = detect_cards(&image);
+        assert_eq!(detected.len(), 3);
+        for expected in expected_corners {
+            let center_x = expected.iter().map(|p| p.x).sum::<f32>() / 4.0;
+            let found = detected
+                .iter()
+                .find(|card| (card.corners.iter().map(|p| p.x).sum::<f32>() / 4.0 - center_x).abs() < 10.0)
+                .expect("Card not detected");
+            for corner in found.corners {
+                let nearest = expected.iter().map(|&e| distance(e, corner)).fold(f32::MAX, f32::min);
+                assert!(nearest < 8.0, "Corner {:?} too far from the card outline", corner);
+            }
+            assert_eq!(found.crop.dimensions(), (CARD_CROP_WIDTH, CARD_CROP_HEIGHT));
+        }
+    }
+
+    #[test]
+    fn test_crop_is_upright_card() {
+        let (image, _) = synthetic_table(&[card("1GFo")]);
+        let detected = detect_cards(&image);
+        assert_eq!(detected.len(), 1);
+
+        // The center of the crop shows the solid oval, its corners the white card
+        let crop = &detected[0].crop;
+        let center = crop.get_pixel(CARD_CROP_WIDTH / 2, CARD_CROP_HEIGHT / 2);
+        assert!(center[1] > center[0] && center[1] > center[2]);
+        let corner = crop.get_pixel(10, 10);
+        assert!(corner.0.iter().all(|&channel| channel > 200));
+    }
+}
@@ -0,0 +1,339 @@
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use imageproc::contours::Contour;
+use imageproc::drawing::draw_polygon_mut;
+use imageproc::point::Point;
+
+use super::{Classified, DetectedCard};
+use crate::Color;
+
+// Hue angles in the a*b* plane of the Lab color space, in degrees
+const RED_HUE: f32 = 35.0;
+const GREEN_HUE: f32 = 145.0;
+const PURPLE_HUE: f32 = 315.0;
+
+// Clusters with hues closer than this are the same color
+const MIN_CLUSTER_SEPARATION: f32 = 40.0;
+// Fraction of the most colorful pixels inside a symbol that are taken to be its ink
+const INK_FRACTION: f32 = 0.1;
+const KMEANS_ITERATIONS: usize = 20;
+
+/// A color in the CIE L*a*b* color space, where distances match perceived differences
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    pub fn from_rgb(rgb: Rgb<u8>) -> Self {
+        // sRGB to linear RGB to XYZ, relative to the D65 white point
+        let linear = rgb.0.map(|channel| {
+            let c = channel as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        let [r, g, b] = linear;
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let f = |t: f32| {
+            if t > 0.008856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+        Lab {
+            l: 116.0 * f(y) - 16.0,
+            a: 500.0 * (f(x) - f(y)),
+            b: 200.0 * (f(y) - f(z)),
+        }
+    }
+
+    pub fn chroma(&self) -> f32 {
+        self.a.hypot(self.b)
+    }
+
+    pub fn mean(colors: &[Lab]) -> Lab {
+        let n = colors.len() as f32;
+        Lab {
+            l: colors.iter().map(|c| c.l).sum::<f32>() / n,
+            a: colors.iter().map(|c| c.a).sum::<f32>() / n,
+            b: colors.iter().map(|c| c.b).sum::<f32>() / n,
+        }
+    }
+
+    /// Hue angle in degrees, between 0 and 360
+    pub fn hue(&self) -> f32 {
+        self.b.atan2(self.a).to_degrees().rem_euclid(360.0)
+    }
+}
+
+fn hue_distance(a: f32, b: f32) -> f32 {
+    let difference = (a - b).rem_euclid(360.0);
+    difference.min(360.0 - difference)
+}
+
+fn reference_hue(color: Color) -> f32 {
+    match color {
+        Color::Red => RED_HUE,
+        Color::Green => GREEN_HUE,
+        Color::Purple => PURPLE_HUE,
+    }
+}
+
+/// The ink color of the symbol with outline `points`.
+/// Its hue is corrected for the color of the card around it, which should be white,
+/// so a tinted light does not change the outcome.
+pub fn symbol_color(image: &RgbImage, points: &[Point<i32>]) -> Option<Lab> {
+    let min_x = points.iter().map(|p| p.x).min()?.max(0);
+    let max_x = points.iter().map(|p| p.x).max()?.min(image.width() as i32 - 1);
+    let min_y = points.iter().map(|p| p.y).min()?.max(0);
+    let max_y = points.iter().map(|p| p.y).max()?.min(image.height() as i32 - 1);
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+
+    let mut mask = GrayImage::new((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32);
+    let local: Vec<Point<i32>> = points.iter().map(|p| Point::new(p.x - min_x, p.y - min_y)).collect();
+    draw_polygon_mut(&mut mask, &local, Luma([255]));
+
+    let mut inside = vec![];
+    let mut around = vec![];
+    for (x, y, pixel) in mask.enumerate_pixels() {
+        let lab = Lab::from_rgb(*image.get_pixel(x + min_x as u32, y + min_y as u32));
+        if pixel[0] > 0 {
+            inside.push(lab);
+        } else {
+            around.push(lab);
+        }
+    }
+    if inside.is_empty() || around.is_empty() {
+        return None;
+    }
+
+    // The most colorful pixels inside are the ink, the least colorful ones around it are the card
+    inside.sort_by(|p, q| q.chroma().total_cmp(&p.chroma()));
+    around.sort_by(|p, q| p.chroma().total_cmp(&q.chroma()));
+    let ink = Lab::mean(&inside[..((inside.len() as f32 * INK_FRACTION) as usize).max(1)]);
+    let card = Lab::mean(&around[..(around.len() / 4).max(1)]);
+    Some(Lab {
+        l: ink.l,
+        a: ink.a - card.a,
+        b: ink.b - card.b,
+    })
+}
+
+fn chromatic_distance(p: &Lab, q: &Lab) -> f32 {
+    (p.a - q.a).hypot(p.b - q.b)
+}
+
+/// Cluster the colors in the a*b* plane, ignoring lightness which depends most on the lighting.
+/// Returns the cluster centers and the cluster of each sample.
+fn kmeans(samples: &[Lab], k: usize) -> (Vec<Lab>, Vec<usize>) {
+    // Start with the most different samples: the most colorful one, then each time the one farthest from the chosen
+    let mut centers = vec![*samples.iter().max_by(|p, q| p.chroma().total_cmp(&q.chroma())).unwrap()];
+    while centers.len() < k.min(samples.len()) {
+        let farthest = samples
+            .iter()
+            .max_by(|p, q| {
+                let distance = |s: &Lab| centers.iter().map(|c| chromatic_distance(s, c)).fold(f32::MAX, f32::min);
+                distance(p).total_cmp(&distance(q))
+            })
+            .unwrap();
+        centers.push(*farthest);
+    }
+
+    let mut assignment = vec![0; samples.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        for (sample, cluster) in samples.iter().zip(assignment.iter_mut()) {
+            *cluster = (0..centers.len())
+                .min_by(|&i, &j| chromatic_distance(sample, &centers[i]).total_cmp(&chromatic_distance(sample, &centers[j])))
+                .unwrap();
+        }
+        for (index, center) in centers.iter_mut().enumerate() {
+            let members: Vec<Lab> = samples
+                .iter()
+                .zip(&assignment)
+                .filter(|(_, &cluster)| cluster == index)
+                .map(|(sample, _)| *sample)
+                .collect();
+            if !members.is_empty() {
+                *center = Lab::mean(&members);
+            }
+        }
+    }
+    (centers, assignment)
+}
+
+/// Group the symbol colors of the whole table in (at most) 3 clusters and name each cluster
+/// after the color with the nearest hue, using every color only once.
+pub fn cluster_colors(samples: &[Lab]) -> Vec<Classified<Color>> {
+    if samples.is_empty() {
+        return vec![];
+    }
+    // Fewer clusters when they turn out to be the same color, e.g. when all cards on the table are red
+    let (centers, assignment) = (1..=3)
+        .rev()
+        .map(|k| kmeans(samples, k))
+        .find(|(centers, _)| {
+            centers.iter().enumerate().all(|(i, p)| {
+                centers[i + 1..]
+                    .iter()
+                    .all(|q| hue_distance(p.hue(), q.hue()) >= MIN_CLUSTER_SEPARATION)
+            })
+        })
+        .unwrap();
+
+    // Try all ways of naming the clusters, keep the one that fits the hues best
+    let colors = [Color::Red, Color::Green, Color::Purple];
+    let permutations = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+    let cost = |permutation: &[usize; 3]| -> f32 {
+        centers
+            .iter()
+            .zip(permutation)
+            .map(|(center, &color)| hue_distance(center.hue(), reference_hue(colors[color])))
+            .sum()
+    };
+    let naming = permutations
+        .iter()
+        .min_by(|p, q| cost(p).total_cmp(&cost(q)))
+        .unwrap();
+
+    samples
+        .iter()
+        .zip(&assignment)
+        .map(|(sample, &cluster)| {
+            let color = colors[naming[cluster]];
+            let own = chromatic_distance(sample, &centers[cluster]);
+            let confidence = match centers
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != cluster)
+                .map(|(_, center)| chromatic_distance(sample, center))
+                .reduce(f32::min)
+            {
+                Some(other) if own + other > 0.0 => other / (own + other),
+                // A single cluster, how well does it match the hue of its color?
+                _ => 1.0 - hue_distance(sample.hue(), reference_hue(color)) / 180.0,
+            };
+            Classified { value: color, confidence }
+        })
+        .collect()
+}
+
+/// Colors of the symbols on each of the cards found by `detect_cards_in_contours` in these contours,
+/// clustered together so all cards are judged by the same standard.
+pub fn classify_table_colors(
+    image: &RgbImage,
+    contours: &[Contour<i32>],
+    cards: &[DetectedCard],
+) -> Vec<Vec<Classified<Color>>> {
+    let mut samples = vec![];
+    let mut owners = vec![];
+    for (card, detected) in cards.iter().enumerate() {
+        for &symbol_index in &detected.symbols {
+            if let Some(sample) = symbol_color(image, &contours[symbol_index].points) {
+                samples.push(sample);
+                owners.push(card);
+            }
+        }
+    }
+
+    let mut colors = vec![vec![]; cards.len()];
+    for (classified, card) in cluster_colors(&samples).into_iter().zip(owners) {
+        colors[card].push(classified);
+    }
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::synthetic::{card, ink, render_card, symbol_centers, symbol_polygon, synthetic_table};
+    use crate::vision::contours_and_cards;
+
+    fn tinted(color: Rgb<u8>, tint: [i16; 3]) -> Rgb<u8> {
+        Rgb([0, 1, 2].map(|i| (color[i] as i16 + tint[i]).clamp(0, 255) as u8))
+    }
+
+    #[test]
+    fn test_lab_from_rgb() {
+        let white = Lab::from_rgb(Rgb([255, 255, 255]));
+        assert!((white.l - 100.0).abs() < 0.5);
+        assert!(white.chroma() < 0.5);
+        let black = Lab::from_rgb(Rgb([0, 0, 0]));
+        assert!(black.l.abs() < 0.5);
+    }
+
+    #[test]
+    fn test_reference_hues() {
+        for color in Color::iterator() {
+            let hue = Lab::from_rgb(ink(*color)).hue();
+            assert!(hue_distance(hue, reference_hue(*color)) < 20.0, "{:?} has hue {}", color, hue);
+        }
+    }
+
+    #[test]
+    fn test_cluster_colors_under_tinted_light() {
+        // Yellowish light shifts all hues, but the clusters stay apart
+        let tint = [20, 10, -30];
+        let inks = [Color::Purple, Color::Red, Color::Red, Color::Green, Color::Purple, Color::Green];
+        let samples: Vec<Lab> = inks.iter().map(|color| Lab::from_rgb(tinted(ink(*color), tint))).collect();
+        let classified = cluster_colors(&samples);
+        let colors: Vec<Color> = classified.iter().map(|c| c.value).collect();
+        assert_eq!(colors, inks);
+        assert!(classified.iter().all(|c| c.confidence > 0.5));
+    }
+
+    #[test]
+    fn test_cluster_single_color() {
+        let samples: Vec<Lab> = [Rgb([220, 30, 40]), Rgb([200, 40, 50]), Rgb([230, 20, 30])]
+            .map(Lab::from_rgb)
+            .to_vec();
+        assert!(cluster_colors(&samples).iter().all(|c| c.value == Color::Red));
+    }
+
+    #[test]
+    fn test_symbol_color_of_solid_symbol() {
+        // A solid oval covers most of its bounding box, and none of it may be taken for the card
+        for notation in ["1RFo", "1GFo", "1PFo"] {
+            let card = card(notation);
+            let (x, y) = symbol_centers(&card)[0];
+            let mut image = render_card(&card);
+            for pixel in image.pixels_mut() {
+                *pixel = tinted(*pixel, [-30, 0, 40]);
+            }
+            let color = symbol_color(&image, &symbol_polygon(card.shape, x, y)).unwrap();
+            let ink = Lab::from_rgb(tinted(ink(card.color), [-30, 0, 40]));
+            let white = Lab::from_rgb(*image.get_pixel(0, 0));
+            assert!((color.a - (ink.a - white.a)).abs() < 1.0, "{notation}: {color:?}");
+            assert!((color.b - (ink.b - white.b)).abs() < 1.0, "{notation}: {color:?}");
+        }
+    }
+
+    #[test]
+    fn test_classify_table_colors() {
+        let cards = [card("1RFd"), card("2GOo"), card("3PSs"), card("2RSo")];
+        let (image, _) = synthetic_table(&cards);
+        let (contours, detected) = contours_and_cards(&image);
+        assert_eq!(detected.len(), 4);
+
+        let colors = classify_table_colors(&image, &contours, &detected);
+        for (detected_card, symbol_colors) in detected.iter().zip(colors) {
+            // Match the detected card to the synthetic one by its position
+            let center_x = detected_card.corners.iter().map(|p| p.x).sum::<f32>() / 4.0;
+            let center_y = detected_card.corners.iter().map(|p| p.y).sum::<f32>() / 4.0;
+            let column = ((center_x - 20.0) / 170.0) as usize;
+            let row = ((center_y - 20.0) / 230.0) as usize;
+            let expected = cards[row * 4 + column];
+            assert!(!symbol_colors.is_empty());
+            assert!(symbol_colors.iter().all(|c| c.value == expected.color));
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use image::{GrayImage, Rgb, RgbImage};
+use imageproc::contours::Contour;
+use imageproc::drawing::draw_polygon_mut;
+use imageproc::point::Point;
+
+use super::{detect_cards_in_contours, CANNY_HIGH, CANNY_LOW};
+use crate::tree::ContourTree;
+
+// Colors of the contours, by how deep they are nested
+const LEVEL_COLORS: [Rgb<u8>; 8] = [
+    Rgb([0, 0, 0]),
+    Rgb([0, 0, 255]),
+    Rgb([0, 255, 0]),
+    Rgb([0, 255, 255]),
+    Rgb([255, 0, 0]),
+    Rgb([255, 0, 255]),
+    Rgb([255, 255, 0]),
+    Rgb([255, 255, 255]),
+];
+const CARD_COLOR: Rgb<u8> = Rgb([0, 0, 0]);
+
+/// The intermediate images of the vision pipeline, to see where it goes wrong
+pub struct DebugImages {
+    pub grayscale: GrayImage,
+    pub canny: GrayImage,
+    /// The photo with all contours drawn over it, colored by level, and the detected cards
+    pub contours: RgbImage,
+    /// Upright crops of the detected cards
+    pub crops: Vec<RgbImage>,
+}
+
+/// Draw the contours, the outer ones first so the nested ones stay visible
+fn draw_contours(image: &mut RgbImage, contours: &[Contour<i32>]) {
+    let tree = ContourTree::new(contours);
+    for index in tree.by_depth() {
+        if contours[index].points.len() > 1 {
+            let color = LEVEL_COLORS[tree.depth(index) % LEVEL_COLORS.len()];
+            draw_polygon_mut(image, &contours[index].points, color);
+        }
+    }
+}
+
+pub fn debug_images(image: &RgbImage) -> DebugImages {
+    let grayscale = image::imageops::grayscale(image);
+    let canny = imageproc::edges::canny(&grayscale, CANNY_LOW, CANNY_HIGH);
+    let contours = imageproc::contours::find_contours(&canny);
+
+    let mut contour_image = image.clone();
+    draw_contours(&mut contour_image, &contours);
+    let detected_cards = detect_cards_in_contours(image, &contours);
+    for card in &detected_cards {
+        let corners = card.corners.map(|p| Point::new(p.x.round() as i32, p.y.round() as i32));
+        draw_polygon_mut(&mut contour_image, &corners, CARD_COLOR);
+    }
+
+    DebugImages {
+        grayscale,
+        canny,
+        contours: contour_image,
+        crops: detected_cards.into_iter().map(|card| card.crop).collect(),
+    }
+}
+
+impl DebugImages {
+    /// Write all images as numbered PNGs in the order of the pipeline, creating `dir` when needed
+    pub fn write_to(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        let numbered = |written: &Vec<PathBuf>, name: &str| dir.join(format!("{:02}-{}.png", written.len() + 1, name));
+
+        let mut written = vec![];
+        for (name, image) in [("grayscale", &self.grayscale), ("canny", &self.canny)] {
+            let path = numbered(&written, name);
+            image.save(&path).map_err(io::Error::other)?;
+            written.push(path);
+        }
+        let crops = self.crops.iter().enumerate().map(|(index, crop)| (format!("card-{}", index + 1), crop));
+        for (name, image) in std::iter::once(("contours".to_string(), &self.contours)).chain(crops) {
+            let path = numbered(&written, &name);
+            image.save(&path).map_err(io::Error::other)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::synthetic::{card, synthetic_table};
+    use crate::vision::{CARD_CROP_HEIGHT, CARD_CROP_WIDTH};
+
+    #[test]
+    fn test_debug_images() {
+        let (image, _) = synthetic_table(&["1RFd", "2GOo"].map(card));
+        let debug = debug_images(&image);
+        assert_eq!(debug.grayscale.dimensions(), image.dimensions());
+        assert_eq!(debug.canny.dimensions(), image.dimensions());
+        assert_ne!(debug.contours, image);
+        assert_eq!(debug.crops.len(), 2);
+        assert_eq!(debug.crops[0].dimensions(), (CARD_CROP_WIDTH, CARD_CROP_HEIGHT));
+    }
+
+    #[test]
+    fn test_write_debug_images() {
+        let (image, _) = synthetic_table(&[card("3PSs")]);
+        let dir = std::env::temp_dir().join(format!("setvision-debug-{}", std::process::id()));
+        let written = debug_images(&image).write_to(&dir).unwrap();
+
+        let names: Vec<_> = written.iter().map(|path| path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["01-grayscale.png", "02-canny.png", "03-contours.png", "04-card-1.png"]);
+        assert!(written.iter().all(|path| image::open(path).is_ok()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
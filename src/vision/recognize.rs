@@ -0,0 +1,149 @@
+use image::RgbImage;
+use imageproc::point::Point;
+
+use super::color::classify_table_colors;
+use super::shading::classify_card_shadings;
+use super::shape::classify_card_shapes;
+use super::{detect_cards_in_contours, Classified, DetectedCard, CANNY_HIGH, CANNY_LOW};
+use crate::{Card, Count, Error};
+
+/// A card recognised in a photo, with where it was found
+#[derive(Debug, Clone)]
+pub struct RecognizedCard {
+    pub card: Card,
+    /// Confidence of the least certain attribute of the card
+    pub confidence: f32,
+    pub detected: DetectedCard,
+}
+
+/// The value with the most confidence over all votes
+fn vote<T: Copy + PartialEq>(votes: &[Classified<T>]) -> Option<Classified<T>> {
+    let mut totals: Vec<(T, f32)> = vec![];
+    for classified in votes {
+        match totals.iter_mut().find(|(value, _)| *value == classified.value) {
+            Some((_, total)) => *total += classified.confidence,
+            None => totals.push((classified.value, classified.confidence)),
+        }
+    }
+    totals
+        .into_iter()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(value, total)| Classified {
+            value,
+            confidence: total / votes.len() as f32,
+        })
+}
+
+fn center(corners: &[Point<f32>; 4]) -> (f32, f32) {
+    (
+        corners.iter().map(|p| p.x).sum::<f32>() / 4.0,
+        corners.iter().map(|p| p.y).sum::<f32>() / 4.0,
+    )
+}
+
+/// Sort cards in rows from top to bottom, and each row from left to right
+fn reading_order(cards: &mut Vec<RecognizedCard>) {
+    cards.sort_by(|a, b| center(&a.detected.corners).1.total_cmp(&center(&b.detected.corners).1));
+
+    let mut rows: Vec<Vec<RecognizedCard>> = vec![];
+    for card in cards.drain(..) {
+        let (_, y) = center(&card.detected.corners);
+        // A card starts a new row when it is more than half a card lower than the previous one
+        let half_height = (card.detected.corners[3].y - card.detected.corners[0].y).abs() / 2.0;
+        match rows.last_mut() {
+            Some(row) if y - center(&row.last().unwrap().detected.corners).1 < half_height => row.push(card),
+            _ => rows.push(vec![card]),
+        }
+    }
+    for mut row in rows {
+        row.sort_by(|a, b| center(&a.detected.corners).0.total_cmp(&center(&b.detected.corners).0));
+        cards.extend(row);
+    }
+}
+
+/// Find and recognise all cards in a photo of a table, in reading order
+pub fn recognize_cards(image: &RgbImage) -> Vec<RecognizedCard> {
+    let grayscaled = image::imageops::grayscale(image);
+    let canny = imageproc::edges::canny(&grayscaled, CANNY_LOW, CANNY_HIGH);
+    let contours = imageproc::contours::find_contours(&canny);
+    let detected_cards = detect_cards_in_contours(image, &contours);
+
+    let colors = classify_table_colors(image, &contours, &detected_cards);
+
+    let mut recognized = vec![];
+    for (detected, symbol_colors) in detected_cards.into_iter().zip(colors) {
+        let count = match detected.symbols.len() {
+            1 => Count::One,
+            2 => Count::Two,
+            3 => Count::Three,
+            _ => continue,
+        };
+        let shapes = classify_card_shapes(&contours, &detected);
+        let shadings = classify_card_shadings(image, &contours, &detected);
+        let (Some(color), Some(shading), Some(shape)) = (vote(&symbol_colors), vote(&shadings), vote(&shapes)) else {
+            continue;
+        };
+
+        recognized.push(RecognizedCard {
+            card: Card {
+                color: color.value,
+                count,
+                shading: shading.value,
+                shape: shape.value,
+            },
+            confidence: color.confidence.min(shading.confidence).min(shape.confidence),
+            detected,
+        });
+    }
+    reading_order(&mut recognized);
+    recognized
+}
+
+/// The cards in a photo of a table, in reading order. Fails when no card is found.
+pub fn recognize_table(image: &RgbImage) -> Result<Vec<Card>, Error> {
+    let cards: Vec<Card> = recognize_cards(image).into_iter().map(|recognized| recognized.card).collect();
+    if cards.is_empty() {
+        return Err(Error::Recognition("no cards found in the image".to_string()));
+    }
+    Ok(cards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::synthetic::{card, synthetic_table};
+    use crate::Shape;
+
+    #[test]
+    fn test_vote() {
+        let votes = [
+            Classified { value: Shape::Oval, confidence: 0.9 },
+            Classified { value: Shape::Diamond, confidence: 0.6 },
+            Classified { value: Shape::Diamond, confidence: 0.6 },
+        ];
+        let winner = vote(&votes).unwrap();
+        assert_eq!(winner.value, Shape::Diamond);
+        assert!((winner.confidence - 0.4).abs() < 1e-6);
+        assert_eq!(vote::<Shape>(&[]), None);
+    }
+
+    #[test]
+    fn test_recognize_table() {
+        let cards: Vec<Card> = ["1RFd", "2GOo", "3PSs", "2RSo", "3GFs", "1POd"].map(card).to_vec();
+        let (image, _) = synthetic_table(&cards);
+        assert_eq!(recognize_table(&image).unwrap(), cards);
+
+        let (empty, _) = synthetic_table(&[]);
+        assert!(matches!(recognize_table(&empty), Err(Error::Recognition(_))));
+    }
+
+    #[test]
+    fn test_recognize_cards_confidence() {
+        let (image, corners) = synthetic_table(&[card("2PSd")]);
+        let recognized = recognize_cards(&image);
+        assert_eq!(recognized.len(), 1);
+        assert!(recognized[0].confidence > 0.5);
+        let (x, _) = center(&recognized[0].detected.corners);
+        assert!((x - center(&corners[0]).0).abs() < 5.0);
+    }
+}
@@ -0,0 +1,243 @@
+use image::{GrayImage, Luma, RgbImage};
+use imageproc::contours::Contour;
+use imageproc::distance_transform::Norm;
+use imageproc::drawing::draw_polygon_mut;
+use imageproc::point::Point;
+
+use super::color::Lab;
+use super::{ramp, Classified, DetectedCard};
+use crate::Shading;
+
+// Pixels that differ this much (in Lab, about 2 is just noticeable) from the card are ink
+const INK_DISTANCE: f32 = 25.0;
+// Part of the height of a symbol that is skipped from its edge, to leave out its outline
+const OUTLINE_MARGIN: f32 = 0.12;
+
+/// Measurements of the inside of a symbol, compared to the card around it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadingFeatures {
+    /// Fraction of the inside covered by ink
+    pub fill_ratio: f32,
+    /// Variance of the difference with the card, high when the inside is partly filled
+    pub variance: f32,
+    /// Number of stripes crossed when walking along the long axis of the symbol
+    pub stripes: usize,
+}
+
+pub fn shading_features(image: &RgbImage, points: &[Point<i32>]) -> Option<ShadingFeatures> {
+    let min_x = points.iter().map(|p| p.x).min()?.max(0);
+    let max_x = points.iter().map(|p| p.x).max()?.min(image.width() as i32 - 1);
+    let min_y = points.iter().map(|p| p.y).min()?.max(0);
+    let max_y = points.iter().map(|p| p.y).max()?.min(image.height() as i32 - 1);
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+    // Keep a border of background around the symbol, otherwise eroding it stops at the edge of the mask
+    let (origin_x, origin_y) = (min_x - 1, min_y - 1);
+    let (width, height) = ((max_x - min_x + 3) as u32, (max_y - min_y + 3) as u32);
+    let in_image = |x: u32, y: u32| {
+        let (x, y) = (x as i32 + origin_x, y as i32 + origin_y);
+        x >= 0 && y >= 0 && x < image.width() as i32 && y < image.height() as i32
+    };
+    let color_at = |x: u32, y: u32| {
+        let pixel = image.get_pixel((x as i32 + origin_x) as u32, (y as i32 + origin_y) as u32);
+        Lab::from_rgb(*pixel)
+    };
+
+    let mut mask = GrayImage::new(width, height);
+    let local: Vec<Point<i32>> = points.iter().map(|p| Point::new(p.x - origin_x, p.y - origin_y)).collect();
+    draw_polygon_mut(&mut mask, &local, Luma([255]));
+
+    // The long axis of the symbol, from the second moments of its area
+    let inside: Vec<(f32, f32)> = mask
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[0] > 0)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    let n = inside.len() as f32;
+    let (center_x, center_y) = (
+        inside.iter().map(|p| p.0).sum::<f32>() / n,
+        inside.iter().map(|p| p.1).sum::<f32>() / n,
+    );
+    let (mut xx, mut yy, mut xy) = (0.0, 0.0, 0.0);
+    for (x, y) in &inside {
+        xx += (x - center_x).powi(2) / n;
+        yy += (y - center_y).powi(2) / n;
+        xy += (x - center_x) * (y - center_y) / n;
+    }
+    let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    let minor_variance = (xx + yy) / 2.0 - (((xx - yy) / 2.0).powi(2) + xy * xy).sqrt();
+    // A band of height h has a variance of h^2 / 12 across it
+    let symbol_height = (12.0 * minor_variance.max(0.0)).sqrt();
+    let margin = (OUTLINE_MARGIN * symbol_height).round().clamp(2.0, 255.0) as u8;
+    let interior = imageproc::morphology::erode(&mask, Norm::LInf, margin);
+
+    // The card is the brightest half of the pixels around the symbol
+    let mut around: Vec<Lab> = mask
+        .enumerate_pixels()
+        .filter(|(x, y, pixel)| pixel[0] == 0 && in_image(*x, *y))
+        .map(|(x, y, _)| color_at(x, y))
+        .collect();
+    if around.is_empty() {
+        return None;
+    }
+    around.sort_by(|p, q| q.l.total_cmp(&p.l));
+    around.truncate((around.len() / 2).max(1));
+    let card = Lab::mean(&around);
+    let difference = |x: u32, y: u32| {
+        let color = color_at(x, y);
+        ((color.l - card.l).powi(2) + (color.a - card.a).powi(2) + (color.b - card.b).powi(2)).sqrt()
+    };
+
+    let differences: Vec<f32> = interior
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[0] > 0)
+        .map(|(x, y, _)| difference(x, y))
+        .collect();
+    if differences.is_empty() {
+        return None;
+    }
+    let count = differences.len() as f32;
+    let mean = differences.iter().sum::<f32>() / count;
+
+    // Walk along the long axis and count how often the inside switches from card to ink
+    let mut ink_starts = 0;
+    let mut previous_ink = None;
+    let reach = (width.max(height)) as f32;
+    let mut t = -reach;
+    while t <= reach {
+        let (x, y) = (center_x + t * angle.cos(), center_y + t * angle.sin());
+        t += 1.0;
+        if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+            continue;
+        }
+        let (x, y) = (x as u32, y as u32);
+        if interior.get_pixel(x, y)[0] == 0 {
+            previous_ink = None;
+            continue;
+        }
+        let ink = difference(x, y) > INK_DISTANCE;
+        if ink && previous_ink == Some(false) {
+            ink_starts += 1;
+        }
+        previous_ink = Some(ink);
+    }
+
+    Some(ShadingFeatures {
+        fill_ratio: differences.iter().filter(|&&d| d > INK_DISTANCE).count() as f32 / count,
+        variance: differences.iter().map(|d| (d - mean).powi(2)).sum::<f32>() / count,
+        stripes: ink_starts,
+    })
+}
+
+/// Open symbols are empty inside and solid ones full. Striped symbols show stripes,
+/// or when those are too fine for the camera, a partly filled inside. Faint stripes that are
+/// rarely taken for ink still make the inside vary, unlike the even inside of an open symbol.
+pub fn classify_features(features: &ShadingFeatures) -> Classified<Shading> {
+    let ink_distance = f64::from(INK_DISTANCE);
+    let striped = ramp(features.stripes as f64, 2.0, 5.0);
+    let some_ink = ramp(features.fill_ratio.into(), 0.05, 0.2);
+    let mostly_ink = ramp(features.fill_ratio.into(), 0.5, 0.85);
+    let uneven = ramp(features.variance.sqrt().into(), 0.3 * ink_distance, 0.6 * ink_distance);
+    let partly_ink = some_ink.max(uneven).min(1.0 - mostly_ink);
+
+    let scores = [
+        (Shading::Open, (1.0 - some_ink) * (1.0 - uneven) * (1.0 - striped)),
+        (Shading::Solid, mostly_ink * (1.0 - striped)),
+        (Shading::Striped, striped.max(partly_ink)),
+    ];
+    Classified::best_of(&scores)
+}
+
+pub fn classify_shading(image: &RgbImage, points: &[Point<i32>]) -> Option<Classified<Shading>> {
+    shading_features(image, points).map(|features| classify_features(&features))
+}
+
+/// Classify the shading of the symbols on a card found by `detect_cards_in_contours` in these contours
+pub fn classify_card_shadings(
+    image: &RgbImage,
+    contours: &[Contour<i32>],
+    card: &DetectedCard,
+) -> Vec<Classified<Shading>> {
+    card.symbols
+        .iter()
+        .filter_map(|&index| classify_shading(image, &contours[index].points))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::synthetic::{card, render_card, symbol_centers, symbol_polygon, synthetic_table};
+    use crate::vision::contours_and_cards;
+    use crate::Card;
+
+    // The outline of the first symbol on an upright rendering of the card
+    fn first_symbol(card: &Card) -> (RgbImage, Vec<Point<i32>>) {
+        let (x, y) = symbol_centers(card)[0];
+        (render_card(card), symbol_polygon(card.shape, x, y))
+    }
+
+    #[test]
+    fn test_shading_features() {
+        let (image, outline) = first_symbol(&card("1RFo"));
+        let solid = shading_features(&image, &outline).unwrap();
+        assert!(solid.fill_ratio > 0.95);
+        assert_eq!(solid.stripes, 0);
+
+        let (image, outline) = first_symbol(&card("1ROo"));
+        let open = shading_features(&image, &outline).unwrap();
+        assert!(open.fill_ratio < 0.05);
+        assert_eq!(open.stripes, 0);
+
+        let (image, outline) = first_symbol(&card("1RSo"));
+        let striped = shading_features(&image, &outline).unwrap();
+        assert!(striped.stripes > 10);
+        assert!(striped.variance > open.variance);
+    }
+
+    #[test]
+    fn test_classify_shading() {
+        for notation in ["1GOd", "1GFd", "1GSd", "2POs", "2PFs", "2PSs", "3ROo", "3RFo", "3RSo"] {
+            let card = card(notation);
+            let (image, outline) = first_symbol(&card);
+            let classified = classify_shading(&image, &outline).unwrap();
+            assert_eq!(classified.value, card.shading, "{}", notation);
+            assert!(classified.confidence > 0.5);
+        }
+    }
+
+    #[test]
+    fn test_classify_card_shadings() {
+        let cards = [card("1RFd"), card("2GOo"), card("3PSs")];
+        let (image, _) = synthetic_table(&cards);
+        let (contours, detected) = contours_and_cards(&image);
+        for detected_card in detected {
+            let shadings = classify_card_shadings(&image, &contours, &detected_card);
+            let expected = cards
+                .iter()
+                .find(|card| {
+                    let count: usize = card.count.into();
+                    count == shadings.len()
+                })
+                .unwrap();
+            assert!(shadings.iter().all(|classified| classified.value == expected.shading));
+        }
+    }
+
+    #[test]
+    fn test_blurred_stripes_are_striped() {
+        let features = ShadingFeatures {
+            fill_ratio: 0.35,
+            variance: 50.0,
+            stripes: 0,
+        };
+        assert_eq!(classify_features(&features).value, Shading::Striped);
+
+        // Stripes so faint that hardly any of them counts as ink
+        let faint = ShadingFeatures { fill_ratio: 0.02, variance: 150.0, stripes: 0 };
+        assert_eq!(classify_features(&faint).value, Shading::Striped);
+        let open = ShadingFeatures { variance: 10.0, ..faint };
+        assert_eq!(classify_features(&open).value, Shading::Open);
+    }
+}
@@ -0,0 +1,160 @@
+use imageproc::contours::Contour;
+use imageproc::geometry::{approximate_polygon_dp, arc_length, convex_hull, min_area_rect};
+use imageproc::point::Point;
+
+use super::{polygon_area, ramp, Classified, DetectedCard};
+use crate::Shape;
+
+// Epsilon for approximating a symbol with a polygon, relative to its perimeter
+const POLYGON_EPSILON: f64 = 0.04;
+
+/// Geometric features of the outline of a symbol
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeFeatures {
+    /// Number of vertices of the approximating polygon
+    pub vertices: usize,
+    /// Area of the symbol relative to the area of its convex hull
+    pub solidity: f64,
+    /// Area of the symbol relative to its minimal bounding rectangle
+    pub extent: f64,
+    /// Long side of the minimal bounding rectangle divided by its short side
+    pub aspect_ratio: f64,
+    /// Deepest convexity defect, relative to the short side of the bounding rectangle
+    pub defect_depth: f64,
+}
+
+pub fn shape_features(points: &[Point<i32>]) -> ShapeFeatures {
+    let area = polygon_area(points);
+    let hull = convex_hull(points);
+    let hull_area = polygon_area(&hull);
+
+    let rect = min_area_rect(points);
+    let side = |a: Point<i32>, b: Point<i32>| (((a.x - b.x).pow(2) + (a.y - b.y).pow(2)) as f64).sqrt();
+    let (first_side, second_side) = (side(rect[0], rect[1]), side(rect[1], rect[2]));
+    let (long_side, short_side) = (first_side.max(second_side), first_side.min(second_side).max(1.0));
+
+    let epsilon = POLYGON_EPSILON * arc_length(points, true);
+    let vertices = if epsilon > 0.0 {
+        approximate_polygon_dp(points, epsilon, true).len()
+    } else {
+        points.len()
+    };
+
+    // Deepest point of the outline inside its convex hull
+    let defect_depth = points
+        .iter()
+        .map(|&point| {
+            (0..hull.len())
+                .map(|i| distance_to_segment(point, hull[i], hull[(i + 1) % hull.len()]))
+                .fold(f64::MAX, f64::min)
+        })
+        .fold(0.0, f64::max);
+
+    ShapeFeatures {
+        vertices,
+        solidity: if hull_area > 0.0 { area / hull_area } else { 0.0 },
+        extent: area / (long_side * short_side).max(1.0),
+        aspect_ratio: long_side / short_side,
+        defect_depth: defect_depth / short_side,
+    }
+}
+
+fn distance_to_segment(point: Point<i32>, start: Point<i32>, end: Point<i32>) -> f64 {
+    let (px, py) = ((point.x - start.x) as f64, (point.y - start.y) as f64);
+    let (dx, dy) = ((end.x - start.x) as f64, (end.y - start.y) as f64);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
+
+/// A squiggle is the only concave symbol.
+/// Of the convex ones, an oval fills most of its bounding rectangle and a diamond only half.
+pub fn classify_features(features: &ShapeFeatures) -> Classified<Shape> {
+    let concavity = ramp(0.95 - features.solidity, 0.0, 0.15).max(ramp(features.defect_depth, 0.08, 0.2));
+    let roundness = ramp(features.extent, 0.55, 0.8);
+    let corners = if features.vertices == 4 { 1.0 } else { 0.6 };
+
+    let scores = [
+        (Shape::Diamond, (1.0 - concavity) * (1.0 - roundness) * corners),
+        (Shape::Oval, (1.0 - concavity) * roundness),
+        (Shape::Squiggle, concavity),
+    ];
+    Classified::best_of(&scores)
+}
+
+pub fn classify_shape(points: &[Point<i32>]) -> Classified<Shape> {
+    classify_features(&shape_features(points))
+}
+
+/// Classify the symbols on a card found by `detect_cards_in_contours` in these contours
+pub fn classify_card_shapes(contours: &[Contour<i32>], card: &DetectedCard) -> Vec<Classified<Shape>> {
+    card.symbols
+        .iter()
+        .map(|&index| classify_shape(&contours[index].points))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vision::contours_and_cards;
+    use crate::vision::synthetic::{card, symbol_polygon, synthetic_table};
+    use image::{GrayImage, Luma};
+    use imageproc::drawing::draw_polygon_mut;
+
+    fn outline(shape: Shape) -> Vec<Point<i32>> {
+        let mut mask = GrayImage::new(200, 100);
+        draw_polygon_mut(&mut mask, &symbol_polygon(shape, 100.0, 50.0), Luma([255]));
+        let contours: Vec<Contour<i32>> = imageproc::contours::find_contours(&mask);
+        contours.into_iter().next().unwrap().points
+    }
+
+    #[test]
+    fn test_shape_features() {
+        let diamond = shape_features(&outline(Shape::Diamond));
+        assert_eq!(diamond.vertices, 4);
+        assert!(diamond.solidity > 0.95);
+        assert!((diamond.extent - 0.5).abs() < 0.1);
+
+        let oval = shape_features(&outline(Shape::Oval));
+        assert!(oval.extent > 0.8);
+        assert!(oval.aspect_ratio > 2.0);
+
+        let squiggle = shape_features(&outline(Shape::Squiggle));
+        assert!(squiggle.solidity < 0.9);
+        assert!(squiggle.defect_depth > 0.1);
+    }
+
+    #[test]
+    fn test_classify_shape() {
+        for shape in Shape::iterator() {
+            let classified = classify_shape(&outline(*shape));
+            assert_eq!(classified.value, *shape);
+            assert!(classified.confidence > 0.5);
+        }
+    }
+
+    #[test]
+    fn test_classify_card_shapes() {
+        let cards = [card("1RFd"), card("2GOo"), card("3PSs")];
+        let (image, _) = synthetic_table(&cards);
+        let (contours, detected) = contours_and_cards(&image);
+        assert_eq!(detected.len(), 3);
+        for detected_card in detected {
+            let shapes = classify_card_shapes(&contours, &detected_card);
+            // Cards in the synthetic table all have a different number of symbols
+            let expected = cards
+                .iter()
+                .find(|card| {
+                    let count: usize = card.count.into();
+                    count == shapes.len()
+                })
+                .unwrap();
+            assert!(shapes.iter().all(|classified| classified.value == expected.shape));
+        }
+    }
+}
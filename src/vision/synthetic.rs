@@ -0,0 +1,152 @@
+// Synthetic photos of tables, so the vision stages can be tested without real photos
+
+use std::f32::consts::PI;
+
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use imageproc::distance_transform::Norm;
+use imageproc::drawing::draw_polygon_mut;
+use imageproc::geometric_transformations::Projection;
+use imageproc::point::Point;
+
+use super::{CARD_CROP_HEIGHT, CARD_CROP_WIDTH};
+use crate::{Card, Color, Shading, Shape};
+
+pub const SYMBOL_WIDTH: f32 = 120.0;
+pub const SYMBOL_HEIGHT: f32 = 50.0;
+const OUTLINE_WIDTH: u8 = 3;
+const STRIPE_PERIOD: u32 = 6;
+
+const TABLE_COLOR: Rgb<u8> = Rgb([50, 70, 60]);
+const CARD_COLOR: Rgb<u8> = Rgb([245, 245, 240]);
+
+pub fn card(notation: &str) -> Card {
+    notation.parse().unwrap()
+}
+
+pub fn ink(color: Color) -> Rgb<u8> {
+    match color {
+        Color::Red => Rgb([220, 30, 40]),
+        Color::Green => Rgb([20, 150, 60]),
+        Color::Purple => Rgb([110, 40, 140]),
+    }
+}
+
+/// Outline of a horizontal symbol centered around (center_x, center_y)
+pub fn symbol_polygon(shape: Shape, center_x: f32, center_y: f32) -> Vec<Point<i32>> {
+    let (half_width, half_height) = (SYMBOL_WIDTH / 2.0, SYMBOL_HEIGHT / 2.0);
+    let points: Vec<(f32, f32)> = match shape {
+        Shape::Diamond => vec![(-half_width, 0.0), (0.0, -half_height), (half_width, 0.0), (0.0, half_height)],
+        Shape::Oval => {
+            let straight = half_width - half_height;
+            let right = (0..=16).map(|i| {
+                let angle = -PI / 2.0 + PI * i as f32 / 16.0;
+                (straight + half_height * angle.cos(), half_height * angle.sin())
+            });
+            let left = (0..=16).map(|i| {
+                let angle = PI / 2.0 + PI * i as f32 / 16.0;
+                (-straight + half_height * angle.cos(), half_height * angle.sin())
+            });
+            right.chain(left).collect()
+        }
+        Shape::Squiggle => {
+            // A band following a sine wave
+            let wave = |x: f32| 0.2 * SYMBOL_HEIGHT * (2.0 * PI * x / SYMBOL_WIDTH).sin();
+            let xs: Vec<f32> = (0..=24).map(|i| -half_width + SYMBOL_WIDTH * i as f32 / 24.0).collect();
+            let top = xs.iter().map(|&x| (x, -0.6 * half_height + wave(x)));
+            let bottom = xs.iter().rev().map(|&x| (x, 0.6 * half_height + wave(x)));
+            top.chain(bottom).collect()
+        }
+    };
+    points
+        .into_iter()
+        .map(|(x, y)| Point::new((center_x + x).round() as i32, (center_y + y).round() as i32))
+        .collect()
+}
+
+/// Vertical centers of the symbols on an upright card
+pub fn symbol_centers(card: &Card) -> Vec<(f32, f32)> {
+    let count: usize = card.count.into();
+    let spacing = 70.0;
+    let first = CARD_CROP_HEIGHT as f32 / 2.0 - spacing * (count - 1) as f32 / 2.0;
+    (0..count)
+        .map(|i| (CARD_CROP_WIDTH as f32 / 2.0, first + spacing * i as f32))
+        .collect()
+}
+
+/// An upright card of `CARD_CROP_WIDTH` x `CARD_CROP_HEIGHT`
+pub fn render_card(card: &Card) -> RgbImage {
+    let mut image = RgbImage::from_pixel(CARD_CROP_WIDTH, CARD_CROP_HEIGHT, CARD_COLOR);
+    let color = ink(card.color);
+    for (x, y) in symbol_centers(card) {
+        let mut mask = GrayImage::new(CARD_CROP_WIDTH, CARD_CROP_HEIGHT);
+        draw_polygon_mut(&mut mask, &symbol_polygon(card.shape, x, y), Luma([255]));
+        let interior = imageproc::morphology::erode(&mask, Norm::LInf, OUTLINE_WIDTH);
+        for (px, py, pixel) in mask.enumerate_pixels() {
+            if pixel[0] == 0 {
+                continue;
+            }
+            let inside = interior.get_pixel(px, py)[0] > 0;
+            let inked = match card.shading {
+                Shading::Solid => true,
+                Shading::Open => !inside,
+                Shading::Striped => !inside || px % STRIPE_PERIOD < 2,
+            };
+            if inked {
+                image.put_pixel(px, py, color);
+            }
+        }
+    }
+    image
+}
+
+/// Paint an upright card image onto `scene`, with its corners ending up at `corners`
+pub fn place_card(scene: &mut RgbImage, card_image: &RgbImage, corners: [Point<f32>; 4]) {
+    let (width, height) = (card_image.width() as f32, card_image.height() as f32);
+    let from = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    let projection = Projection::from_control_points(from, corners.map(|p| (p.x, p.y))).unwrap();
+    let inverse = projection.invert();
+
+    let min_x = corners.iter().map(|p| p.x).fold(f32::MAX, f32::min).max(0.0) as u32;
+    let max_x = corners.iter().map(|p| p.x).fold(0.0, f32::max).min(scene.width() as f32 - 1.0) as u32;
+    let min_y = corners.iter().map(|p| p.y).fold(f32::MAX, f32::min).max(0.0) as u32;
+    let max_y = corners.iter().map(|p| p.y).fold(0.0, f32::max).min(scene.height() as f32 - 1.0) as u32;
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let (u, v) = inverse * (x as f32 + 0.5, y as f32 + 0.5);
+            if u >= 0.0 && v >= 0.0 && u < width && v < height {
+                scene.put_pixel(x, y, *card_image.get_pixel(u as u32, v as u32));
+            }
+        }
+    }
+}
+
+/// A photo of the cards in rows of 4, each card slightly rotated.
+/// Returns the photo and the corners of each card, clockwise from the top-left.
+pub fn synthetic_table(cards: &[Card]) -> (RgbImage, Vec<[Point<f32>; 4]>) {
+    let (card_width, card_height) = (120.0, 187.0);
+    let (spacing_x, spacing_y) = (170.0, 230.0);
+    let columns = cards.len().clamp(1, 4);
+    let rows = cards.len().div_ceil(columns);
+    let mut scene = RgbImage::from_pixel(
+        (spacing_x * columns as f32 + 40.0) as u32,
+        (spacing_y * rows as f32 + 40.0) as u32,
+        TABLE_COLOR,
+    );
+
+    let mut all_corners = vec![];
+    for (index, card) in cards.iter().enumerate() {
+        let center_x = 20.0 + spacing_x * ((index % columns) as f32 + 0.5);
+        let center_y = 20.0 + spacing_y * ((index / columns) as f32 + 0.5);
+        let angle: f32 = if index % 2 == 0 { 0.12 } else { -0.08 };
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].map(|(dx, dy)| {
+            let (x, y) = (dx * card_width / 2.0, dy * card_height / 2.0);
+            Point::new(
+                center_x + x * angle.cos() - y * angle.sin(),
+                center_y + x * angle.sin() + y * angle.cos(),
+            )
+        });
+        place_card(&mut scene, &render_card(card), corners);
+        all_corners.push(corners);
+    }
+    (scene, all_corners)
+}
@@ -0,0 +1,214 @@
+//! Recognition accuracy on the labelled images in `test/`.
+//!
+//! Every image with a `.cards` file next to it is recognised, and the cards found are compared
+//! with the cards in that file, written like `2RSo` as for the `solve` command, in any order.
+//! The accuracy of each attribute may not drop below what is recorded in `test/baseline.txt`.
+//! Run with `SETVISION_UPDATE_BASELINE=1` to record the current accuracy as the new baseline.
+//!
+//! The photos are stored in Git LFS, so they are only checked with `SETVISION_PHOTOS=1`, after a
+//! `git lfs pull`. The other labelled images, like those in `test/synthetic`, are always checked.
+//! Every checked image must decode and have a baseline.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use setvision::vision::recognize_table;
+use setvision::{parse_table, Card};
+
+const ATTRIBUTES: [&str; 4] = ["count", "color", "shading", "shape"];
+const IMAGE_EXTENSIONS: [&str; 3] = ["jpg", "jpeg", "png"];
+// Room for rounding the accuracy in the baseline file
+const TOLERANCE: f64 = 0.005;
+
+/// Fraction of the expected cards of which each attribute was recognised correctly
+type Accuracy = [f64; 4];
+
+fn test_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("test")
+}
+
+/// Whether the file is a Git LFS pointer instead of the image itself
+fn is_lfs_pointer(path: &Path) -> bool {
+    fs::read(path).is_ok_and(|contents| contents.starts_with(b"version https://git-lfs"))
+}
+
+/// Whether the image is a photo, stored in Git LFS
+fn is_photo(path: &Path) -> bool {
+    !path.starts_with(test_dir().join("synthetic"))
+}
+
+fn baseline_path() -> PathBuf {
+    test_dir().join("baseline.txt")
+}
+
+/// Path of the image relative to `test/`, as in the baseline
+fn image_name(path: &Path) -> String {
+    path.strip_prefix(test_dir()).unwrap().to_string_lossy().replace('\\', "/")
+}
+
+/// Count, color, shading and shape of a card, in the order of its notation
+fn attributes(card: &Card) -> Vec<char> {
+    card.notation().chars().collect()
+}
+
+/// Images with ground truth under `dir`, with the path to their `.cards` file
+fn labelled_images(dir: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut labelled = vec![];
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            labelled.extend(labelled_images(&path));
+        } else if path.extension().is_some_and(|extension| extension == "cards") {
+            let image = IMAGE_EXTENSIONS
+                .iter()
+                .map(|extension| path.with_extension(extension))
+                .find(|image| image.exists())
+                .unwrap_or_else(|| panic!("No image for {}", path.display()));
+            labelled.push((image, path));
+        }
+    }
+    labelled
+}
+
+/// Pair each expected card with the recognised card that has the most attributes in common,
+/// best matches first. Expected cards that were not found count as wrong in every attribute.
+fn accuracy(expected: &[Card], recognized: &[Card]) -> Accuracy {
+    let mut pairs = vec![];
+    for (e, expected_card) in expected.iter().enumerate() {
+        for (r, recognized_card) in recognized.iter().enumerate() {
+            let same: Vec<bool> = attributes(expected_card)
+                .into_iter()
+                .zip(attributes(recognized_card))
+                .map(|(a, b)| a == b)
+                .collect();
+            pairs.push((e, r, same));
+        }
+    }
+    pairs.sort_by_key(|(e, r, same)| (std::cmp::Reverse(same.iter().filter(|&&s| s).count()), *e, *r));
+
+    let mut correct = [0usize; 4];
+    let (mut expected_used, mut recognized_used) = (vec![false; expected.len()], vec![false; recognized.len()]);
+    for (e, r, same) in pairs {
+        if expected_used[e] || recognized_used[r] {
+            continue;
+        }
+        expected_used[e] = true;
+        recognized_used[r] = true;
+        for (count, same) in correct.iter_mut().zip(same) {
+            *count += same as usize;
+        }
+    }
+    correct.map(|count| count as f64 / expected.len().max(1) as f64)
+}
+
+fn read_baseline() -> BTreeMap<String, Accuracy> {
+    let Ok(contents) = fs::read_to_string(baseline_path()) else {
+        return BTreeMap::new();
+    };
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(fields.len(), 1 + ATTRIBUTES.len(), "Malformed baseline line: {}", line);
+            let mut accuracy = [0.0; 4];
+            for (value, field) in accuracy.iter_mut().zip(&fields[1..]) {
+                *value = field.parse().unwrap();
+            }
+            (fields[0].to_string(), accuracy)
+        })
+        .collect()
+}
+
+fn write_baseline(baseline: &BTreeMap<String, Accuracy>) {
+    let mut contents = format!("# image {}\n", ATTRIBUTES.join(" "));
+    for (image, accuracy) in baseline {
+        let values: Vec<String> = accuracy.iter().map(|value| format!("{:.2}", value)).collect();
+        contents += &format!("{} {}\n", image, values.join(" "));
+    }
+    fs::write(baseline_path(), contents).unwrap();
+}
+
+#[test]
+fn test_accuracy() {
+    let expected = parse_table("1RFd 2GOo 3PSs").unwrap();
+    assert_eq!(accuracy(&expected, &parse_table("3PSs 1RFd 2GOo").unwrap()), [1.0; 4]);
+
+    // The purple card was recognised as red, and one card was missed
+    let accuracy = accuracy(&expected, &parse_table("2GOo 3RSs").unwrap());
+    let third = 1.0 / 3.0;
+    assert_eq!(accuracy, [2.0 * third, third, 2.0 * third, 2.0 * third]);
+}
+
+#[test]
+fn test_recognition_accuracy() {
+    let update = std::env::var_os("SETVISION_UPDATE_BASELINE").is_some();
+    let photos = std::env::var_os("SETVISION_PHOTOS").is_some();
+    let mut baseline = read_baseline();
+    let mut failures = vec![];
+    let mut skipped = vec![];
+    let labelled = labelled_images(&test_dir());
+
+    println!("{:<40} {}", "image", ATTRIBUTES.map(|name| format!("{:>8}", name)).join(""));
+    for (image_path, cards_path) in &labelled {
+        let name = image_name(image_path);
+        let expected = parse_table(&fs::read_to_string(cards_path).unwrap())
+            .unwrap_or_else(|error| panic!("{}: {}", cards_path.display(), error));
+        if is_photo(image_path) && !photos {
+            skipped.push(name);
+            continue;
+        }
+        let image = match image::open(image_path) {
+            Ok(image) => image.to_rgb8(),
+            Err(_) if is_lfs_pointer(image_path) => {
+                failures.push(format!("{}: not fetched from Git LFS, run `git lfs pull`", name));
+                continue;
+            }
+            Err(error) => {
+                failures.push(format!("{}: cannot decode: {}", name, error));
+                continue;
+            }
+        };
+
+        let measured = accuracy(&expected, &recognize_table(&image).unwrap_or_default());
+        println!("{:<40} {}", name, measured.map(|value| format!("{:>8.2}", value)).join(""));
+
+        if update {
+            baseline.insert(name, measured);
+            continue;
+        }
+        let Some(minimum) = baseline.get(&name) else {
+            failures.push(format!("{}: no baseline, record one with SETVISION_UPDATE_BASELINE=1", name));
+            continue;
+        };
+        for ((attribute, value), minimum) in ATTRIBUTES.iter().zip(measured).zip(minimum) {
+            if value + TOLERANCE < *minimum {
+                failures.push(format!("{}: {} accuracy {:.2} < {:.2}", name, attribute, value, minimum));
+            }
+        }
+    }
+
+    // A baseline for an image that is gone would never be checked
+    let names: Vec<String> = labelled.iter().map(|(image_path, _)| image_name(image_path)).collect();
+    for name in baseline.keys().filter(|name| !names.contains(name)) {
+        failures.push(format!("{}: in the baseline, but there is no labelled image", name));
+    }
+
+    if update {
+        write_baseline(&baseline);
+    }
+    if !skipped.is_empty() {
+        eprintln!(
+            "Skipped {} labelled photos, set SETVISION_PHOTOS=1 to check them: {}",
+            skipped.len(),
+            skipped.join(", ")
+        );
+    }
+    assert!(failures.is_empty(), "Recognition failed:\n{}", failures.join("\n"));
+}